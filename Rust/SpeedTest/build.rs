@@ -0,0 +1,31 @@
+use std::env;
+use std::process::Command;
+
+fn main() {
+    // Record the compiler version so result records can say what built them.
+    let rustc = env::var("RUSTC").unwrap_or_else(|_| "rustc".to_string());
+    let version = Command::new(rustc)
+        .arg("--version")
+        .output()
+        .ok()
+        .and_then(|out| String::from_utf8(out.stdout).ok())
+        .map(|s| s.trim().to_string())
+        .unwrap_or_else(|| "unknown".to_string());
+    println!("cargo:rustc-env=SPEEDTEST_RUSTC_VERSION={}", version);
+    println!("cargo:rerun-if-changed=build.rs");
+
+    // The C programs' kernels, linked into one static library so they can
+    // be timed in-process. The programs' own `main`s are left out, and their
+    // unused timing variables would only add warnings. No contraction into
+    // fused multiply-adds, so the C pi kernel rounds like the Rust one.
+    const SOURCES: [&str; 3] = ["loop_test.c", "function_call.c", "monte_carlo_pi.c"];
+    cc::Build::new()
+        .files(SOURCES)
+        .define("SPEEDTEST_NO_MAIN", None)
+        .flag_if_supported("-ffp-contract=off")
+        .warnings(false)
+        .compile("speedtest_c");
+    for source in SOURCES {
+        println!("cargo:rerun-if-changed={}", source);
+    }
+}
@@ -11,17 +11,22 @@ def quad_res(n, m):
             return 1 # Found a square, so n is a quadratic residue
     return 0 # No such i found
 
+def count_quad_res(m):
+    # The counting loop on its own, so the Rust harness can also call it in-process
+    number_of_QR = 0
+    for n_val in range(m):
+        number_of_QR += quad_res(n_val, m)
+    return number_of_QR
+
 def main():
     print("Starting Python function call test (Quadratic Residues)")
 
     start_time = time.perf_counter() # High-resolution timer
 
     m = 5000  # Modulus
-    number_of_QR = 0
 
     # Test numbers 'n_val' from 0 up to m-1
-    for n_val in range(m): 
-        number_of_QR += quad_res(n_val, m)
+    number_of_QR = count_quad_res(m)
 
     end_time = time.perf_counter()
     
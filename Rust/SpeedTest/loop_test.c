@@ -5,6 +5,19 @@
 // #define CLOCK_TYPE CLOCK_MONOTONIC_RAW
 #define CLOCK_TYPE CLOCK_MONOTONIC
 
+// The kernel on its own, so the Rust harness can also call it through FFI
+long long loop_sum(long long outer_limit, long long inner_limit, long long modulus) {
+    long long sum = 0;
+    for (long long i = 1; i < outer_limit; i++) {
+        for (long long j = 1; j < inner_limit; j++) {
+            sum = (sum + i + j) % modulus;
+        }
+    }
+    return sum;
+}
+
+// The harness links the kernel without this program's main
+#ifndef SPEEDTEST_NO_MAIN
 int main() {
     printf("Starting C loop test\n");
 
@@ -18,11 +31,7 @@ int main() {
 
     clock_gettime(CLOCK_TYPE, &start_ts); // Start timer
 
-    for (long long i = 1; i < outer_limit; i++) {
-        for (long long j = 1; j < inner_limit; j++) {
-            sum = (sum + i + j) % 100000;
-        }
-    }
+    sum = loop_sum(outer_limit, inner_limit, 100000);
 
     clock_gettime(CLOCK_TYPE, &end_ts); // Stop timer
 
@@ -35,4 +44,5 @@ int main() {
 
     return 0;
 }
+#endif
 
@@ -1,5 +1,13 @@
 import time
 
+def loop_sum(outer, inner, modulus):
+    # The kernel on its own, so the Rust harness can also call it in-process
+    sum_val = 0
+    for i in range(1, outer):
+        for j in range(1, inner):
+            sum_val = (sum_val + i + j) % modulus
+    return sum_val
+
 def main():
     print("Starting loop test")
 
@@ -11,9 +19,7 @@ def main():
     # Python's range(start, end) goes up to end-1.
     # Rust's 1..1000 goes from 1 up to (and including) 999.
     # So range(1, 1000) in Python is equivalent.
-    for i in range(1, 1000): 
-        for j in range(1, 1000):
-            sum_val = (sum_val + i + j) % 100000
+    sum_val = loop_sum(1000, 1000, 100000)
 
     end_time = time.perf_counter()
     duration_ns = (end_time - start_time) * 1_000_000_000 # Convert seconds to nanoseconds
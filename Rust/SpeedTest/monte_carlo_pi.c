@@ -7,12 +7,32 @@
 #define CLOCK_MONOTONIC 1 // Fallback for some systems, though POSIX standard
 #endif
 
+// The kernel on its own, so the Rust harness can also call it through FFI.
+// It draws from rand() as seeded by the caller.
+long long count_inside_circle(long long iterations) {
+    long long points_inside_circle = 0;
+    double x, y;
+
+    for (long long i = 0; i < iterations; i++) {
+        // Generate random x, y between 0.0 and 1.0
+        // rand() returns int between 0 and RAND_MAX
+        x = (double)rand() / RAND_MAX;
+        y = (double)rand() / RAND_MAX;
+
+        if ((x * x) + (y * y) <= 1.0) {
+            points_inside_circle++;
+        }
+    }
+    return points_inside_circle;
+}
+
+// The harness links the kernel without this program's main
+#ifndef SPEEDTEST_NO_MAIN
 int main() {
     printf("Starting C Monte Carlo Pi test\n");
 
     long long iterations = 10000000; // 10 million iterations
     long long points_inside_circle = 0;
-    double x, y;
     double mc_pi;
 
     // Seed the random number generator
@@ -22,16 +42,7 @@ int main() {
     struct timespec start_ts, end_ts;
     clock_gettime(CLOCK_MONOTONIC, &start_ts); // Start timer
 
-    for (long long i = 0; i < iterations; i++) {
-        // Generate random x, y between 0.0 and 1.0
-        // rand() returns int between 0 and RAND_MAX
-        x = (double)rand() / RAND_MAX;
-        y = (double)rand() / RAND_MAX;
-
-        if ((x * x) + (y * y) <= 1.0) {
-            points_inside_circle++;
-        }
-    }
+    points_inside_circle = count_inside_circle(iterations);
 
     clock_gettime(CLOCK_MONOTONIC, &end_ts); // Stop timer
 
@@ -51,5 +62,6 @@ int main() {
 
     return 0;
 }
+#endif
 
 
@@ -1,6 +1,17 @@
 import time
 import random # Python's built-in random module
 
+def count_inside_circle(iterations):
+    # The kernel on its own, so the Rust harness can also call it in-process.
+    # It draws from the random module as seeded by the caller.
+    inside = 0
+    for _ in range(iterations):
+        x = random.random()
+        y = random.random()
+        if x*x + y*y <= 1.0:
+            inside += 1
+    return inside
+
 def main():
     print("Starting Python Monte Carlo Pi test")
 
@@ -12,12 +23,8 @@ def main():
     
     iterations_to_run = 10000000 # Matching your Rust loop 0..1000
 
-    for _ in range(iterations_to_run): # Loop 'iterations_to_run' times
-        x = random.random() # Generates a float uniformly in [0.0, 1.0)
-        y = random.random()
-        num_iter += 1
-        if x*x + y*y <= 1.0:
-            inside += 1
+    inside = count_inside_circle(iterations_to_run)
+    num_iter = iterations_to_run
     
     # Ensure num_iter is not zero to avoid DivisionByZeroError if iterations_to_run was 0
     if num_iter == 0:
@@ -0,0 +1,117 @@
+//! Named baselines saved to disk and compared against later runs.
+//!
+//! A baseline is the list of [`Record`]s from one `speedtest run`, stored as
+//! `<dir>/<name>.json`.
+
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+
+use crate::report::Record;
+use crate::stats::{mann_whitney_u, median_of_sorted};
+
+/// Default directory baselines are stored in, relative to the working
+/// directory.
+pub const DEFAULT_DIR: &str = ".speedtest/baselines";
+
+/// Checks a baseline name, for use as a clap `value_parser`. Names become
+/// file names inside the baseline directory, so they may not be empty or
+/// contain path separators or `..`.
+pub fn parse_name(name: &str) -> Result<String, String> {
+    if name.is_empty() {
+        Err("baseline name is empty".to_string())
+    } else if name.contains(['/', '\\']) || name.contains("..") {
+        Err(format!(
+            "baseline name `{}` may not contain `/`, `\\` or `..`",
+            name
+        ))
+    } else {
+        Ok(name.to_string())
+    }
+}
+
+pub fn path(dir: &Path, name: &str) -> io::Result<PathBuf> {
+    let name = parse_name(name).map_err(|e| io::Error::new(io::ErrorKind::InvalidInput, e))?;
+    Ok(dir.join(format!("{}.json", name)))
+}
+
+pub fn save(dir: &Path, name: &str, records: &[Record]) -> io::Result<PathBuf> {
+    let path = path(dir, name)?;
+    fs::create_dir_all(dir)?;
+    let json = serde_json::to_string_pretty(records).map_err(io::Error::other)?;
+    fs::write(&path, json)?;
+    Ok(path)
+}
+
+/// Reads a saved baseline. Every record must have at least one sample, which
+/// a hand-edited or truncated file may not.
+pub fn load(dir: &Path, name: &str) -> io::Result<Vec<Record>> {
+    let path = path(dir, name)?;
+    let json = fs::read_to_string(&path)
+        .map_err(|e| io::Error::new(e.kind(), format!("{}: {}", path.display(), e)))?;
+    let records: Vec<Record> = serde_json::from_str(&json)
+        .map_err(|e| io::Error::other(format!("{}: {}", path.display(), e)))?;
+    if let Some(empty) = records.iter().find(|r| r.samples_ns.is_empty()) {
+        return Err(io::Error::new(
+            io::ErrorKind::InvalidData,
+            format!("{}: `{}` has no samples", path.display(), empty.benchmark),
+        ));
+    }
+    Ok(records)
+}
+
+/// How a benchmark's timings moved between a baseline and a new run.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Verdict {
+    /// Significantly slower by more than the threshold.
+    Regressed,
+    /// Significantly faster by more than the threshold.
+    Improved,
+    /// Not significant, or within the threshold.
+    Unchanged,
+}
+
+/// One benchmark's comparison against its baseline.
+#[derive(Debug, Clone)]
+pub struct Change {
+    pub benchmark: String,
+    pub base_median_ns: f64,
+    pub new_median_ns: f64,
+    /// Relative change of the median, in percent; positive is slower.
+    pub change_pct: f64,
+    pub p_value: f64,
+    pub verdict: Verdict,
+}
+
+/// Compares two records of the same benchmark. A change counts only when the
+/// Mann-Whitney p-value is below `alpha` and the median moved by more than
+/// `threshold_pct` percent.
+pub fn diff(base: &Record, new: &Record, alpha: f64, threshold_pct: f64) -> Change {
+    let a: Vec<f64> = base.samples_ns.iter().map(|&x| x as f64).collect();
+    let b: Vec<f64> = new.samples_ns.iter().map(|&x| x as f64).collect();
+    let base_median_ns = median(&a);
+    let new_median_ns = median(&b);
+    let change_pct = (new_median_ns / base_median_ns - 1.0) * 100.0;
+    let p_value = mann_whitney_u(&a, &b).p_value;
+    let verdict = if p_value >= alpha || change_pct.abs() <= threshold_pct {
+        Verdict::Unchanged
+    } else if change_pct > 0.0 {
+        Verdict::Regressed
+    } else {
+        Verdict::Improved
+    };
+    Change {
+        benchmark: new.benchmark.clone(),
+        base_median_ns,
+        new_median_ns,
+        change_pct,
+        p_value,
+        verdict,
+    }
+}
+
+fn median(samples: &[f64]) -> f64 {
+    let mut sorted = samples.to_vec();
+    sorted.sort_by(f64::total_cmp);
+    median_of_sorted(&sorted)
+}
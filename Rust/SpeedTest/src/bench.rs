@@ -0,0 +1,307 @@
+use std::fmt;
+use std::str::FromStr;
+use std::time::{Duration, Instant};
+
+use serde::{Deserialize, Serialize};
+
+use crate::memory::{self, HeapSnapshot, MemoryUsage, ProcSnapshot};
+use crate::perf::{Counters, Event, PerfCounters};
+use crate::stats::Summary;
+
+/// A single speed test that the `speedtest` runner can time.
+pub trait Benchmark {
+    /// Short name used to select the test on the command line.
+    fn name(&self) -> &'static str;
+
+    /// Other names the test answers to, e.g. the original file stem.
+    fn aliases(&self) -> &'static [&'static str] {
+        &[]
+    }
+
+    /// One line describing what the test exercises.
+    fn description(&self) -> &'static str;
+
+    /// The problem-size parameters the kernel runs with, e.g. `m = 5000`.
+    fn params(&self) -> Vec<(&'static str, String)> {
+        Vec::new()
+    }
+
+    /// Sets one of the parameters listed by [`Benchmark::params`].
+    fn set_param(&mut self, key: &str, value: &str) -> Result<(), ParamError> {
+        let _ = value;
+        Err(ParamError::Unknown(key.to_string()))
+    }
+
+    /// The parameter a scaling sweep varies, e.g. `m`.
+    fn size_param(&self) -> &'static str;
+
+    /// A copy of this benchmark with the same parameters.
+    fn clone_box(&self) -> Box<dyn Benchmark>;
+
+    /// A copy of this benchmark whose work is `factor` times larger.
+    fn scaled(&self, factor: u64) -> Box<dyn Benchmark>;
+
+    /// Units of work one run performs, e.g. inner-loop iterations.
+    fn work(&self) -> f64;
+
+    /// What one unit of [`Benchmark::work`] is, e.g. `call`, for tests whose
+    /// time per unit is the figure of interest; reports then show it.
+    fn work_unit(&self) -> Option<&'static str> {
+        None
+    }
+
+    /// The test this one is a variant of, e.g. `loop`. When both run,
+    /// reports give this test's time per unit of work relative to it.
+    fn reference(&self) -> Option<&'static str> {
+        None
+    }
+
+    /// Runs the kernel once and returns the answer it computed.
+    fn run(&self) -> Value;
+
+    /// What a correct run must return, worked out without the kernel.
+    fn expected(&self) -> Expected;
+
+    /// The sampling uncertainty of `value`, for tests whose answer is a
+    /// statistical estimate rather than an exact result.
+    fn uncertainty(&self, value: Value) -> Option<Uncertainty> {
+        let _ = value;
+        None
+    }
+
+    fn matches(&self, name: &str) -> bool {
+        self.name() == name || self.aliases().contains(&name)
+    }
+}
+
+/// The answer a benchmark kernel computed.
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[serde(untagged)]
+pub enum Value {
+    Int(i64),
+    Float(f64),
+}
+
+impl fmt::Display for Value {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Value::Int(v) => write!(f, "{}", v),
+            Value::Float(v) => write!(f, "{:.6}", v),
+        }
+    }
+}
+
+/// Standard error and 95% confidence interval of an estimated answer.
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct Uncertainty {
+    pub std_error: f64,
+    pub ci95_low: f64,
+    pub ci95_high: f64,
+}
+
+impl Uncertainty {
+    /// The normal-approximation interval `estimate +/- 1.96 std_error`.
+    pub fn normal(estimate: f64, std_error: f64) -> Self {
+        let half = 1.959_963_984_540_054 * std_error;
+        Uncertainty {
+            std_error,
+            ci95_low: estimate - half,
+            ci95_high: estimate + half,
+        }
+    }
+}
+
+/// Why a parameter could not be set.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ParamError {
+    /// The benchmark has no parameter with this name.
+    Unknown(String),
+    /// The value did not parse or is out of range.
+    Invalid { key: String, reason: String },
+    /// The value is fine in general, but this benchmark cannot run with it,
+    /// e.g. a composite modulus for a method that needs a prime.
+    Unsupported { key: String, reason: String },
+}
+
+impl fmt::Display for ParamError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ParamError::Unknown(key) => write!(f, "unknown parameter `{}`", key),
+            ParamError::Invalid { key, reason } => write!(f, "invalid `{}`: {}", key, reason),
+            ParamError::Unsupported { key, reason } => {
+                write!(f, "unsupported `{}`: {}", key, reason)
+            }
+        }
+    }
+}
+
+/// Parses a numeric parameter that must be at least `min`.
+pub fn parse_param<T>(key: &str, value: &str, min: T) -> Result<T, ParamError>
+where
+    T: FromStr + PartialOrd + fmt::Display,
+    T::Err: fmt::Display,
+{
+    let invalid = |reason: String| ParamError::Invalid {
+        key: key.to_string(),
+        reason,
+    };
+    let v: T = value.parse().map_err(|e: T::Err| invalid(e.to_string()))?;
+    if v < min {
+        return Err(invalid(format!("must be at least {}", min)));
+    }
+    Ok(v)
+}
+
+/// An oracle for a benchmark's answer.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Expected {
+    /// The answer must be exactly this.
+    Exact(Value),
+    /// The answer must lie within `tolerance` of `target`.
+    Within { target: f64, tolerance: f64 },
+}
+
+impl Expected {
+    pub fn check(&self, value: Value) -> Result<(), String> {
+        match (*self, value) {
+            (Expected::Exact(want), got) if want == got => Ok(()),
+            (Expected::Exact(want), got) => Err(format!("expected {}, got {}", want, got)),
+            (Expected::Within { target, tolerance }, Value::Float(got))
+                if (got - target).abs() <= tolerance =>
+            {
+                Ok(())
+            }
+            (Expected::Within { target, tolerance }, got) => Err(format!(
+                "expected {:.6} +/- {:.6}, got {}",
+                target, tolerance, got
+            )),
+        }
+    }
+}
+
+/// The outcome of one timed run.
+#[derive(Debug, Clone, Copy)]
+pub struct Sample {
+    pub value: Value,
+    pub elapsed: Duration,
+}
+
+/// Times a single run of `bench`.
+pub fn time(bench: &dyn Benchmark) -> Sample {
+    let start_time = Instant::now();
+    let value = bench.run();
+    let elapsed = start_time.elapsed();
+    Sample { value, elapsed }
+}
+
+/// How many times to run a benchmark.
+#[derive(Debug, Clone, Copy)]
+pub struct RunConfig {
+    /// Untimed runs before measuring, to warm caches and the CPU governor.
+    pub warmup: usize,
+    /// Timed runs that feed the statistics.
+    pub runs: usize,
+    /// Collect hardware performance counters around the timed runs.
+    pub counters: bool,
+    /// Record resident set size, page faults and heap allocations.
+    pub memory: bool,
+}
+
+impl Default for RunConfig {
+    fn default() -> Self {
+        RunConfig {
+            warmup: 1,
+            runs: 10,
+            counters: false,
+            memory: false,
+        }
+    }
+}
+
+/// The outcome of a full measurement: every timed run plus their summary.
+#[derive(Debug, Clone)]
+pub struct Measurement {
+    /// The answer from the last timed run.
+    pub value: Value,
+    pub samples: Vec<Duration>,
+    pub summary: Summary,
+    /// Per-run counter averages, when requested and at least one counter
+    /// could be opened.
+    pub counters: Option<Counters>,
+    /// Requested counters that could not be opened, with the reason.
+    pub missing_counters: Vec<String>,
+    /// Memory use over the timed runs, when requested.
+    pub memory: Option<MemoryUsage>,
+}
+
+/// Runs `bench` `config.warmup` times untimed, then `config.runs` times timed.
+pub fn measure(bench: &dyn Benchmark, config: &RunConfig) -> Measurement {
+    assert!(config.runs > 0, "at least one timed run is required");
+    for _ in 0..config.warmup {
+        bench.run();
+    }
+    let perf = config.counters.then(PerfCounters::open);
+    if let Some(perf) = &perf {
+        perf.reset();
+    }
+    let mut samples = Vec::with_capacity(config.runs);
+    let mut value = None;
+    // Taken last, so the harness's own allocations stay out of the counts.
+    // Heap counting is on only from here to `since` below.
+    let proc_before = config.memory.then(ProcSnapshot::take);
+    let heap_before = (config.memory && memory::counting()).then(HeapSnapshot::take);
+    for _ in 0..config.runs {
+        // The counters are switched on outside the timed region so the
+        // ioctls do not show up in the timings.
+        if let Some(perf) = &perf {
+            perf.enable();
+        }
+        let sample = time(bench);
+        if let Some(perf) = &perf {
+            perf.disable();
+        }
+        samples.push(sample.elapsed);
+        value = Some(sample.value);
+    }
+    let heap = heap_before.map(|h| h.since(config.runs));
+    let memory = proc_before.map(|p| {
+        let mut usage = p.since(config.runs);
+        usage.heap = heap.map(|mut heap| {
+            heap.allocates_in_loop =
+                heap.allocations > 0.0 && memory::allocates_in_loop(bench, &heap);
+            heap
+        });
+        usage
+    });
+    let summary = Summary::from_durations(&samples);
+    let (counters, missing_counters) = match &perf {
+        Some(perf) => {
+            let counters = perf.read(config.runs);
+            // Counters can open fine yet never get scheduled, e.g. in VMs
+            // without a virtual PMU; report those as missing too.
+            let missing = Event::ALL
+                .iter()
+                .filter(|&&event| counters.get(event).is_none())
+                .map(|&event| {
+                    let reason = perf
+                        .unavailable
+                        .iter()
+                        .find(|(e, _)| *e == event)
+                        .map_or_else(|| "never counted".to_string(), |(_, err)| err.to_string());
+                    format!("{}: {}", event.name(), reason)
+                })
+                .collect();
+            let any = Event::ALL.iter().any(|&e| counters.get(e).is_some());
+            (any.then_some(counters), missing)
+        }
+        None => (None, Vec::new()),
+    };
+    Measurement {
+        value: value.expect("runs > 0"),
+        samples,
+        summary,
+        counters,
+        missing_counters,
+        memory,
+    }
+}
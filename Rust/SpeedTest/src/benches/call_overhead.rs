@@ -0,0 +1,332 @@
+use std::hint::black_box;
+
+use crate::bench::parse_param;
+use crate::benches::function_call::quad_res;
+use crate::ffi::{c_quad_res_raw, C_QUAD_RES_MAX_M};
+use crate::{Benchmark, Expected, ParamError, Value};
+
+/// How the call-overhead kernels reach their callee.
+///
+/// The flat kinds make `calls` calls to a callee that adds its two
+/// arguments, so little but the call itself is timed. The recursive kinds
+/// count calls as written in the source; the compiler is free to turn some
+/// of them into loops, and that is part of what they measure. The
+/// `quad_res` kinds call `quad_res(i, 1)` from Rust and, across the FFI
+/// boundary, from C.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Call {
+    /// The callee is inlined; the baseline with no call at all.
+    Inline,
+    /// A direct call to an `#[inline(never)]` function.
+    NeverInline,
+    /// An indirect call through an `fn` pointer.
+    FnPointer,
+    /// Virtual dispatch through a `&dyn Trait`.
+    Dyn,
+    /// A capturing closure passed to a generic function.
+    Closure,
+    /// Naive recursive Fibonacci.
+    Fib,
+    /// The Ackermann function `A(3, n)`.
+    Ackermann,
+    /// The Rust `quad_res`, not inlined.
+    QuadRes,
+    /// The C `quad_res` through FFI.
+    Ffi,
+}
+
+impl Call {
+    fn is_recursive(self) -> bool {
+        matches!(self, Call::Fib | Call::Ackermann)
+    }
+
+    /// Largest `n` for the recursive kinds. `fib(n)` makes about
+    /// `3.6 * 1.618^n` calls, some 330 million at `n = 40`, so a run still
+    /// ends in seconds; `A(3, n)` recurses `2^(n+3)` frames deep, which past
+    /// `n = 12` risks the stack.
+    fn max_n(self) -> u32 {
+        match self {
+            Call::Ackermann => 12,
+            _ => 40,
+        }
+    }
+}
+
+/// Times one way of calling a function, reported per call.
+#[derive(Debug, Clone, Copy)]
+pub struct CallOverhead {
+    pub call: Call,
+    /// Calls per run for the flat kinds.
+    pub calls: u64,
+    /// The argument of the recursive kinds.
+    pub n: u32,
+}
+
+impl CallOverhead {
+    pub fn new(call: Call) -> Self {
+        CallOverhead {
+            call,
+            calls: 10_000_000,
+            n: match call {
+                Call::Ackermann => 8,
+                _ => 30,
+            },
+        }
+    }
+}
+
+impl Benchmark for CallOverhead {
+    fn name(&self) -> &'static str {
+        match self.call {
+            Call::Inline => "call_inline",
+            Call::NeverInline => "call_never_inline",
+            Call::FnPointer => "call_fn_pointer",
+            Call::Dyn => "call_dyn",
+            Call::Closure => "call_closure",
+            Call::Fib => "call_fib",
+            Call::Ackermann => "call_ackermann",
+            Call::QuadRes => "call_quad_res",
+            Call::Ffi => "call_ffi",
+        }
+    }
+
+    fn description(&self) -> &'static str {
+        match self.call {
+            Call::Inline => "sum 0..calls through an inlined function",
+            Call::NeverInline => "sum 0..calls through an #[inline(never)] function",
+            Call::FnPointer => "sum 0..calls through an fn pointer",
+            Call::Dyn => "sum 0..calls through a &dyn Trait method",
+            Call::Closure => "sum 0..calls through a capturing closure",
+            Call::Fib => "naive recursive Fibonacci F(n)",
+            Call::Ackermann => "recursive Ackermann function A(3, n)",
+            Call::QuadRes => "call the Rust quad_res(i, 1) `calls` times",
+            Call::Ffi => "call the C quad_res(i, 1) through FFI `calls` times",
+        }
+    }
+
+    fn params(&self) -> Vec<(&'static str, String)> {
+        if self.call.is_recursive() {
+            vec![("n", self.n.to_string())]
+        } else {
+            vec![("calls", self.calls.to_string())]
+        }
+    }
+
+    fn set_param(&mut self, key: &str, value: &str) -> Result<(), ParamError> {
+        match key {
+            "calls" if !self.call.is_recursive() => self.calls = parse_param(key, value, 1)?,
+            "n" if self.call.is_recursive() => {
+                let n = parse_param(key, value, 0)?;
+                if n > self.call.max_n() {
+                    return Err(ParamError::Invalid {
+                        key: key.to_string(),
+                        reason: format!("at most {}", self.call.max_n()),
+                    });
+                }
+                self.n = n;
+            }
+            _ => return Err(ParamError::Unknown(key.to_string())),
+        }
+        Ok(())
+    }
+
+    fn size_param(&self) -> &'static str {
+        if self.call.is_recursive() {
+            "n"
+        } else {
+            "calls"
+        }
+    }
+
+    fn clone_box(&self) -> Box<dyn Benchmark> {
+        Box::new(*self)
+    }
+
+    /// The recursive kinds grow `n` by the logarithm of `factor`, in base
+    /// phi for Fibonacci and 4 for Ackermann, so [`Benchmark::work`] grows
+    /// about `factor` times.
+    fn scaled(&self, factor: u64) -> Box<dyn Benchmark> {
+        let factor = factor as f64;
+        let n = match self.call {
+            Call::Fib => self.n + (factor.ln() / 1.618_033_988_749_895f64.ln()).round() as u32,
+            Call::Ackermann => self.n + (factor.ln() / 4f64.ln()).round() as u32,
+            _ => self.n,
+        };
+        Box::new(CallOverhead {
+            calls: (self.calls as f64 * factor) as u64,
+            n: n.min(self.call.max_n()),
+            ..*self
+        })
+    }
+
+    /// The number of calls.
+    fn work(&self) -> f64 {
+        match self.call {
+            Call::Fib => fib_calls(self.n) as f64,
+            Call::Ackermann => ackermann_calls(self.n) as f64,
+            _ => self.calls as f64,
+        }
+    }
+
+    fn work_unit(&self) -> Option<&'static str> {
+        Some("call")
+    }
+
+    fn run(&self) -> Value {
+        let calls = black_box(self.calls);
+        let n = black_box(self.n);
+        let result = match self.call {
+            Call::Inline => sum_inline(calls),
+            Call::NeverInline => sum_never_inline(calls),
+            Call::FnPointer => sum_fn_pointer(calls, black_box(add as fn(u64, u64) -> u64)),
+            Call::Dyn => sum_dyn(calls, black_box(&Add as &dyn Step)),
+            Call::Closure => {
+                let offset = black_box(0);
+                sum_closure(calls, |acc, i| acc.wrapping_add(i + offset))
+            }
+            Call::Fib => fib(n),
+            Call::Ackermann => ackermann(3, n as u64),
+            Call::QuadRes => {
+                // Opaque so the constant cannot be propagated into the callee.
+                let m = black_box(1);
+                sum_calls(calls, |i| rust_quad_res(i as i64, m) as u64)
+            }
+            Call::Ffi => {
+                let m = black_box(1);
+                // `c_quad_res` checks `m` on every call; check it once here
+                // so only the call itself is timed.
+                assert!(m <= C_QUAD_RES_MAX_M);
+                // SAFETY: `quad_res` only does arithmetic, and `m` is small
+                // enough that none of it overflows.
+                sum_calls(calls, |i| unsafe { c_quad_res_raw(i as i64, m) } as u64)
+            }
+        };
+        Value::Int(black_box(result) as i64)
+    }
+
+    fn expected(&self) -> Expected {
+        let result = match self.call {
+            Call::Fib => fib_iterative(self.n),
+            Call::Ackermann => (1 << (self.n + 3)) - 3,
+            // Every `i` is a residue mod 1.
+            Call::QuadRes | Call::Ffi => self.calls,
+            _ => (self.calls as u128 * (self.calls as u128 - 1) / 2) as u64,
+        };
+        Expected::Exact(Value::Int(result as i64))
+    }
+}
+
+#[inline(always)]
+fn add_inline(acc: u64, i: u64) -> u64 {
+    acc.wrapping_add(i)
+}
+
+#[inline(never)]
+fn add(acc: u64, i: u64) -> u64 {
+    acc.wrapping_add(i)
+}
+
+trait Step {
+    fn step(&self, acc: u64, i: u64) -> u64;
+}
+
+struct Add;
+
+impl Step for Add {
+    #[inline(never)]
+    fn step(&self, acc: u64, i: u64) -> u64 {
+        acc.wrapping_add(i)
+    }
+}
+
+// Each `i` goes through `black_box`, so the sums cannot be folded or
+// vectorised and every variant does one add per call.
+
+fn sum_inline(calls: u64) -> u64 {
+    let mut acc = 0;
+    for i in 0..calls {
+        acc = add_inline(acc, black_box(i));
+    }
+    acc
+}
+
+fn sum_never_inline(calls: u64) -> u64 {
+    let mut acc = 0;
+    for i in 0..calls {
+        acc = add(acc, black_box(i));
+    }
+    acc
+}
+
+fn sum_fn_pointer(calls: u64, f: fn(u64, u64) -> u64) -> u64 {
+    let mut acc = 0;
+    for i in 0..calls {
+        acc = f(acc, black_box(i));
+    }
+    acc
+}
+
+fn sum_dyn(calls: u64, step: &dyn Step) -> u64 {
+    let mut acc = 0;
+    for i in 0..calls {
+        acc = step.step(acc, black_box(i));
+    }
+    acc
+}
+
+fn sum_closure<F: Fn(u64, u64) -> u64>(calls: u64, f: F) -> u64 {
+    let mut acc = 0;
+    for i in 0..calls {
+        acc = f(acc, black_box(i));
+    }
+    acc
+}
+
+fn sum_calls<F: Fn(u64) -> u64>(calls: u64, f: F) -> u64 {
+    let mut acc: u64 = 0;
+    for i in 0..calls {
+        acc = acc.wrapping_add(f(black_box(i)));
+    }
+    acc
+}
+
+#[inline(never)]
+fn rust_quad_res(n: i64, m: i64) -> i64 {
+    quad_res(n, m)
+}
+
+pub fn fib(n: u32) -> u64 {
+    if n < 2 {
+        n as u64
+    } else {
+        fib(n - 1) + fib(n - 2)
+    }
+}
+
+fn fib_iterative(n: u32) -> u64 {
+    let (mut a, mut b) = (0u64, 1u64);
+    for _ in 0..n {
+        (a, b) = (b, a.wrapping_add(b));
+    }
+    a
+}
+
+/// Calls `fib(n)` makes, `2 F(n+1) - 1`.
+fn fib_calls(n: u32) -> u128 {
+    2 * fib_iterative(n + 1) as u128 - 1
+}
+
+pub fn ackermann(m: u64, n: u64) -> u64 {
+    if m == 0 {
+        n + 1
+    } else if n == 0 {
+        ackermann(m - 1, 1)
+    } else {
+        ackermann(m - 1, ackermann(m, n - 1))
+    }
+}
+
+/// Calls `ackermann(3, n)` makes, `(128 * 4^n - 120 * 2^n + 9n + 37) / 3`.
+fn ackermann_calls(n: u32) -> u128 {
+    (128 * 4u128.pow(n) - 120 * 2u128.pow(n) + 9 * n as u128 + 37) / 3
+}
@@ -0,0 +1,122 @@
+use std::hint::black_box;
+
+use crate::bench::parse_param;
+use crate::residue;
+use crate::{Benchmark, Expected, ParamError, Value};
+
+/// The faster ways of counting quadratic residues, see [`crate::residue`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Method {
+    Sieve,
+    Euler,
+    Jacobi,
+    Crt,
+}
+
+impl Method {
+    /// Euler's criterion and the Legendre symbol decide residues only for
+    /// prime moduli.
+    fn needs_prime(self) -> bool {
+        matches!(self, Method::Euler | Method::Jacobi)
+    }
+}
+
+/// Counts the quadratic residues mod `m` like `function_call`, with one of
+/// the [`Method`]s in place of the naive `quad_res`.
+#[derive(Debug, Clone, Copy)]
+pub struct FastQuadRes {
+    pub method: Method,
+    pub m: u64,
+}
+
+impl FastQuadRes {
+    /// A prime default so all four methods run at the same size.
+    pub fn new(method: Method) -> Self {
+        FastQuadRes {
+            method,
+            m: 1_000_003,
+        }
+    }
+}
+
+impl Benchmark for FastQuadRes {
+    fn name(&self) -> &'static str {
+        match self.method {
+            Method::Sieve => "quad_res_sieve",
+            Method::Euler => "quad_res_euler",
+            Method::Jacobi => "quad_res_jacobi",
+            Method::Crt => "quad_res_crt",
+        }
+    }
+
+    fn description(&self) -> &'static str {
+        match self.method {
+            Method::Sieve => "count quadratic residues mod m by marking all squares in one pass",
+            Method::Euler => "count quadratic residues mod prime m by Euler's criterion",
+            Method::Jacobi => "count quadratic residues mod prime m by Legendre/Jacobi symbols",
+            Method::Crt => "count quadratic residues mod m by factorising m and using the CRT",
+        }
+    }
+
+    fn params(&self) -> Vec<(&'static str, String)> {
+        vec![("m", self.m.to_string())]
+    }
+
+    fn set_param(&mut self, key: &str, value: &str) -> Result<(), ParamError> {
+        match key {
+            "m" => {
+                let m = parse_param(key, value, 1)?;
+                if self.method.needs_prime() && !residue::is_prime(m) {
+                    return Err(ParamError::Unsupported {
+                        key: key.to_string(),
+                        reason: format!(
+                            "{} is not prime; quad_res_crt handles composite moduli",
+                            m
+                        ),
+                    });
+                }
+                self.m = m;
+            }
+            _ => return Err(ParamError::Unknown(key.to_string())),
+        }
+        Ok(())
+    }
+
+    fn size_param(&self) -> &'static str {
+        "m"
+    }
+
+    fn clone_box(&self) -> Box<dyn Benchmark> {
+        Box::new(*self)
+    }
+
+    /// The prime-only methods move on to the next prime.
+    fn scaled(&self, factor: u64) -> Box<dyn Benchmark> {
+        let mut m = self.m * factor;
+        if self.method.needs_prime() {
+            while !residue::is_prime(m) {
+                m += 1;
+            }
+        }
+        Box::new(FastQuadRes { m, ..*self })
+    }
+
+    fn work(&self) -> f64 {
+        self.m as f64
+    }
+
+    fn run(&self) -> Value {
+        let m = black_box(self.m);
+        let count = match self.method {
+            Method::Sieve => residue::count_sieve(m),
+            Method::Euler => residue::count_euler(m),
+            Method::Jacobi => residue::count_jacobi(m),
+            Method::Crt => residue::count_crt(m),
+        };
+        Value::Int(black_box(count as i64))
+    }
+
+    fn expected(&self) -> Expected {
+        Expected::Exact(Value::Int(residue::count_closed_form(self.m) as i64))
+    }
+}
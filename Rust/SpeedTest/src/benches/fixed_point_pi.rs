@@ -0,0 +1,190 @@
+use std::hint::black_box;
+
+use crate::bench::parse_param;
+use crate::benches::monte_carlo_pi::{
+    parse_backend, pi_from_count, pi_uncertainty, standard_error,
+};
+use crate::rng::{Backend, RngFn, UnitRng};
+use crate::{Benchmark, Expected, ParamError, Uncertainty, Value};
+
+/// How the circle test is computed.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Arith {
+    /// `x*x + y*y <= R^2` on the raw 32-bit draws in u64, with `R = 2^32`.
+    Fixed,
+    /// The draws scaled to `[0, 1)` as `f32`.
+    F32,
+    /// The draws scaled to `[0, 1)` as `f64`.
+    F64,
+}
+
+/// The pi estimate from 32-bit integer draws, with the circle test in
+/// fixed-point, `f32` or `f64` arithmetic. All three variants see the same
+/// draws, so the difference in their times is the cost of the arithmetic,
+/// which is large on boards that emulate floating point in software.
+#[derive(Debug, Clone, Copy)]
+pub struct ArithPi {
+    pub arith: Arith,
+    pub iterations: u64,
+    pub rng: Backend,
+    pub seed: u64,
+}
+
+impl ArithPi {
+    pub fn new(arith: Arith) -> Self {
+        ArithPi {
+            arith,
+            iterations: 10_000_000,
+            rng: Backend::ChaCha12,
+            seed: rand::random(),
+        }
+    }
+}
+
+impl Benchmark for ArithPi {
+    fn name(&self) -> &'static str {
+        match self.arith {
+            Arith::Fixed => "monte_carlo_pi_fixed",
+            Arith::F32 => "monte_carlo_pi_f32",
+            Arith::F64 => "monte_carlo_pi_f64",
+        }
+    }
+
+    fn description(&self) -> &'static str {
+        match self.arith {
+            Arith::Fixed => {
+                "estimate pi from 32-bit integer points, circle test in u64 fixed point"
+            }
+            Arith::F32 => "estimate pi from 32-bit integer points, circle test in f32",
+            Arith::F64 => "estimate pi from 32-bit integer points, circle test in f64",
+        }
+    }
+
+    fn params(&self) -> Vec<(&'static str, String)> {
+        vec![
+            ("iterations", self.iterations.to_string()),
+            ("rng", self.rng.to_string()),
+            ("seed", self.seed.to_string()),
+        ]
+    }
+
+    fn set_param(&mut self, key: &str, value: &str) -> Result<(), ParamError> {
+        match key {
+            "iterations" => self.iterations = parse_param(key, value, 1)?,
+            "rng" => self.rng = parse_backend(key, value)?,
+            "seed" => self.seed = parse_param(key, value, 0)?,
+            _ => return Err(ParamError::Unknown(key.to_string())),
+        }
+        Ok(())
+    }
+
+    fn size_param(&self) -> &'static str {
+        "iterations"
+    }
+
+    fn clone_box(&self) -> Box<dyn Benchmark> {
+        Box::new(*self)
+    }
+
+    fn scaled(&self, factor: u64) -> Box<dyn Benchmark> {
+        Box::new(ArithPi {
+            iterations: self.iterations * factor,
+            ..*self
+        })
+    }
+
+    fn work(&self) -> f64 {
+        self.iterations as f64
+    }
+
+    fn run(&self) -> Value {
+        let iterations = black_box(self.iterations);
+        let seed = black_box(self.seed);
+        let inside = match self.arith {
+            Arith::Fixed => self.rng.with_rng(seed, 0, CountFixed { iterations }),
+            Arith::F32 => self.rng.with_rng(seed, 0, CountF32 { iterations }),
+            Arith::F64 => self.rng.with_rng(seed, 0, CountF64 { iterations }),
+        };
+        Value::Float(black_box(pi_from_count(inside, iterations)))
+    }
+
+    fn expected(&self) -> Expected {
+        Expected::Within {
+            target: std::f64::consts::PI,
+            tolerance: 6.0 * standard_error(self.iterations),
+        }
+    }
+
+    fn uncertainty(&self, value: Value) -> Option<Uncertainty> {
+        pi_uncertainty(value, self.iterations)
+    }
+}
+
+/// Whether the point `(x, y) / 2^32` lies in the quarter circle, using only
+/// integer arithmetic. `x*x + y*y` can reach 2^65, but it exceeds
+/// `R^2 = 2^64` exactly when the u64 addition carries, so the carry is the
+/// test; a sum of exactly 2^64 wraps to zero and counts as inside.
+#[inline]
+pub fn inside_fixed(x: u32, y: u32) -> bool {
+    let (x, y) = (x as u64, y as u64);
+    let (sum, carry) = (x * x).overflowing_add(y * y);
+    !carry || sum == 0
+}
+
+struct CountFixed {
+    iterations: u64,
+}
+
+impl RngFn<u64> for CountFixed {
+    fn call<R: UnitRng>(self, rng: &mut R) -> u64 {
+        let mut inside: u64 = 0;
+        for _ in 0..self.iterations {
+            let x = rng.next_u32();
+            let y = rng.next_u32();
+            if inside_fixed(x, y) {
+                inside += 1;
+            }
+        }
+        inside
+    }
+}
+
+struct CountF32 {
+    iterations: u64,
+}
+
+impl RngFn<u64> for CountF32 {
+    fn call<R: UnitRng>(self, rng: &mut R) -> u64 {
+        // The top 24 bits, all an f32 mantissa holds, so the scaling is
+        // exact and the result stays below 1.
+        const SCALE: f32 = 1.0 / (1u32 << 24) as f32;
+        let mut inside: u64 = 0;
+        for _ in 0..self.iterations {
+            let x = (rng.next_u32() >> 8) as f32 * SCALE;
+            let y = (rng.next_u32() >> 8) as f32 * SCALE;
+            if x * x + y * y <= 1.0 {
+                inside += 1;
+            }
+        }
+        inside
+    }
+}
+
+struct CountF64 {
+    iterations: u64,
+}
+
+impl RngFn<u64> for CountF64 {
+    fn call<R: UnitRng>(self, rng: &mut R) -> u64 {
+        const SCALE: f64 = 1.0 / 4_294_967_296.0;
+        let mut inside: u64 = 0;
+        for _ in 0..self.iterations {
+            let x = rng.next_u32() as f64 * SCALE;
+            let y = rng.next_u32() as f64 * SCALE;
+            if x * x + y * y <= 1.0 {
+                inside += 1;
+            }
+        }
+        inside
+    }
+}
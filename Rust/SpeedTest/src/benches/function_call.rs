@@ -0,0 +1,152 @@
+use std::hint::black_box;
+
+use crate::bench::parse_param;
+use crate::benches::Kernel;
+use crate::ffi::{c_quad_res, C_QUAD_RES_MAX_M};
+#[cfg(feature = "python")]
+use crate::python::py_count_quad_res;
+use crate::residue;
+use crate::{Benchmark, Expected, ParamError, Value};
+
+/// Counts the quadratic residues mod `m` by calling `quad_res` for every
+/// candidate, the same as `function_call.c/.py`.
+#[derive(Debug, Clone, Copy)]
+pub struct FunctionCall {
+    pub kernel: Kernel,
+    pub m: i64,
+}
+
+impl Default for FunctionCall {
+    fn default() -> Self {
+        FunctionCall {
+            kernel: Kernel::Rust,
+            m: 5000,
+        }
+    }
+}
+
+impl FunctionCall {
+    pub fn with_kernel(kernel: Kernel) -> Self {
+        FunctionCall {
+            kernel,
+            ..FunctionCall::default()
+        }
+    }
+}
+
+impl Benchmark for FunctionCall {
+    fn name(&self) -> &'static str {
+        match self.kernel {
+            Kernel::Rust => "function_call",
+            Kernel::C => "function_call_c",
+            #[cfg(feature = "python")]
+            Kernel::Python => "function_call_py",
+        }
+    }
+
+    fn aliases(&self) -> &'static [&'static str] {
+        match self.kernel {
+            Kernel::Rust => &["quad_res"],
+            _ => &[],
+        }
+    }
+
+    fn description(&self) -> &'static str {
+        match self.kernel {
+            Kernel::Rust => "count quadratic residues mod m with a naive quad_res(n, m)",
+            Kernel::C => "function_call with quad_res from function_call.c, called through FFI",
+            #[cfg(feature = "python")]
+            Kernel::Python => {
+                "function_call with the loop from function_call.py, in embedded CPython"
+            }
+        }
+    }
+
+    fn params(&self) -> Vec<(&'static str, String)> {
+        vec![("m", self.m.to_string())]
+    }
+
+    fn set_param(&mut self, key: &str, value: &str) -> Result<(), ParamError> {
+        match key {
+            "m" => {
+                let m = parse_param(key, value, 1)?;
+                if self.kernel == Kernel::C && m > C_QUAD_RES_MAX_M {
+                    return Err(ParamError::Invalid {
+                        key: key.to_string(),
+                        reason: format!("the C quad_res overflows above {}", C_QUAD_RES_MAX_M),
+                    });
+                }
+                self.m = m;
+            }
+            _ => return Err(ParamError::Unknown(key.to_string())),
+        }
+        Ok(())
+    }
+
+    fn size_param(&self) -> &'static str {
+        "m"
+    }
+
+    fn clone_box(&self) -> Box<dyn Benchmark> {
+        Box::new(*self)
+    }
+
+    /// The naive count is O(m^2), so `m` grows by the square root of `factor`.
+    fn scaled(&self, factor: u64) -> Box<dyn Benchmark> {
+        let m = (self.m as f64 * (factor as f64).sqrt()).round() as i64;
+        Box::new(FunctionCall { m, ..*self })
+    }
+
+    fn work(&self) -> f64 {
+        (self.m as f64).powi(2)
+    }
+
+    fn run(&self) -> Value {
+        let count = match self.kernel {
+            Kernel::Rust => count_quad_res(black_box(self.m)),
+            Kernel::C => count_with(c_quad_res, black_box(self.m)),
+            #[cfg(feature = "python")]
+            Kernel::Python => py_count_quad_res(black_box(self.m)),
+        };
+        Value::Int(black_box(count))
+    }
+
+    fn expected(&self) -> Expected {
+        Expected::Exact(Value::Int(residue::count_closed_form(self.m as u64) as i64))
+    }
+}
+
+pub fn count_quad_res(m: i64) -> i64 {
+    count_with(quad_res, m)
+}
+
+/// The counting loop of `function_call.c/.py` around either `quad_res`.
+#[inline(always)]
+fn count_with(quad_res: fn(i64, i64) -> i64, m: i64) -> i64 {
+    let mut number_of_qr: i64 = 0;
+    for n in 0..m {
+        number_of_qr += quad_res(n, m);
+    }
+    number_of_qr
+}
+
+/// 1 if `n` is a quadratic residue mod `m`, else 0, trying every `i` like
+/// `function_call.c`: `m <= 0` gives 0 and `n` is first reduced with
+/// `(n % m + m) % m`, so negative `n` work. Unlike C, whose `i * i`
+/// overflows once `m` passes about 3.04e9, squares are widened to 128 bits
+/// above 2^32, so the answer is right for every positive `i64` modulus.
+pub fn quad_res(n: i64, m: i64) -> i64 {
+    if m <= 0 {
+        return 0;
+    }
+    let target = n.rem_euclid(m) as u64;
+    let m = m as u64;
+    // The check sits outside the loops so the common case keeps plain
+    // 64-bit arithmetic.
+    let found = if m <= 1 << 32 {
+        (0..m).any(|i| i * i % m == target)
+    } else {
+        (0..m).any(|i| residue::mul_mod(i, i, m) == target)
+    };
+    found as i64
+}
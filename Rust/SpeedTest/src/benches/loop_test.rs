@@ -0,0 +1,155 @@
+use std::hint::black_box;
+
+use crate::bench::parse_param;
+use crate::benches::Kernel;
+use crate::ffi::c_loop_sum;
+#[cfg(feature = "python")]
+use crate::python::py_loop_sum;
+use crate::{Benchmark, Expected, ParamError, Value};
+
+/// Nested loop with a running modular sum, the same as `loop_test.c/.py`.
+#[derive(Debug, Clone, Copy)]
+pub struct LoopTest {
+    pub kernel: Kernel,
+    pub outer: i64,
+    pub inner: i64,
+    pub modulus: i64,
+}
+
+impl Default for LoopTest {
+    fn default() -> Self {
+        LoopTest {
+            kernel: Kernel::Rust,
+            outer: 1000,
+            inner: 1000,
+            modulus: 100000,
+        }
+    }
+}
+
+impl LoopTest {
+    pub fn with_kernel(kernel: Kernel) -> Self {
+        LoopTest {
+            kernel,
+            ..LoopTest::default()
+        }
+    }
+}
+
+impl Benchmark for LoopTest {
+    fn name(&self) -> &'static str {
+        match self.kernel {
+            Kernel::Rust => "loop",
+            Kernel::C => "loop_c",
+            #[cfg(feature = "python")]
+            Kernel::Python => "loop_py",
+        }
+    }
+
+    fn aliases(&self) -> &'static [&'static str] {
+        match self.kernel {
+            Kernel::Rust => &["loop_test"],
+            Kernel::C => &["loop_test_c"],
+            #[cfg(feature = "python")]
+            Kernel::Python => &["loop_test_py"],
+        }
+    }
+
+    fn description(&self) -> &'static str {
+        match self.kernel {
+            Kernel::Rust => "nested 1..outer x 1..inner loop, sum = (sum + i + j) % modulus",
+            Kernel::C => "loop with the kernel from loop_test.c, called through FFI",
+            #[cfg(feature = "python")]
+            Kernel::Python => "loop with the kernel from loop_test.py, in embedded CPython",
+        }
+    }
+
+    fn params(&self) -> Vec<(&'static str, String)> {
+        vec![
+            ("outer", self.outer.to_string()),
+            ("inner", self.inner.to_string()),
+            ("modulus", self.modulus.to_string()),
+        ]
+    }
+
+    fn set_param(&mut self, key: &str, value: &str) -> Result<(), ParamError> {
+        match key {
+            "outer" => self.outer = parse_param(key, value, 1)?,
+            "inner" => self.inner = parse_param(key, value, 1)?,
+            "modulus" => self.modulus = parse_param(key, value, 1)?,
+            _ => return Err(ParamError::Unknown(key.to_string())),
+        }
+        Ok(())
+    }
+
+    fn size_param(&self) -> &'static str {
+        "outer"
+    }
+
+    fn clone_box(&self) -> Box<dyn Benchmark> {
+        Box::new(*self)
+    }
+
+    fn scaled(&self, factor: u64) -> Box<dyn Benchmark> {
+        Box::new(LoopTest {
+            outer: (self.outer - 1)
+                .saturating_mul(i64::try_from(factor).unwrap_or(i64::MAX))
+                .saturating_add(1),
+            ..*self
+        })
+    }
+
+    fn work(&self) -> f64 {
+        (self.outer - 1) as f64 * (self.inner - 1) as f64
+    }
+
+    fn run(&self) -> Value {
+        let kernel = match self.kernel {
+            Kernel::Rust => loop_sum,
+            Kernel::C => c_loop_sum,
+            #[cfg(feature = "python")]
+            Kernel::Python => py_loop_sum,
+        };
+        let sum = kernel(
+            black_box(self.outer),
+            black_box(self.inner),
+            black_box(self.modulus),
+        );
+        Value::Int(black_box(sum))
+    }
+
+    fn expected(&self) -> Expected {
+        Expected::Exact(Value::Int(loop_sum_closed_form(
+            self.outer,
+            self.inner,
+            self.modulus,
+        )))
+    }
+}
+
+pub fn loop_sum(outer: i64, inner: i64, modulus: i64) -> i64 {
+    let mut sum: i64 = 0;
+    for i in 1..outer {
+        for j in 1..inner {
+            sum = (sum + i + j) % modulus;
+        }
+    }
+    sum
+}
+
+/// The loop's answer without looping. Every term is non-negative, so reducing
+/// at each step gives the same result as reducing the full sum once:
+/// `sum_{i<outer} sum_{j<inner} (i + j) = (inner-1)*T(outer-1) + (outer-1)*T(inner-1)`
+/// where `T(n) = n(n+1)/2`, which is `a b (a + b + 2) / 2` for `a = outer-1`,
+/// `b = inner-1`. That overflows even `i128` for large sizes, so one even
+/// factor is halved and the product is taken mod `modulus`.
+pub fn loop_sum_closed_form(outer: i64, inner: i64, modulus: i64) -> i64 {
+    let a = (outer.max(1) - 1) as u128;
+    let b = (inner.max(1) - 1) as u128;
+    let m = modulus as u128;
+    let mut factors = [a, b, a + b + 2];
+    // If a and b are both odd, a + b + 2 is even.
+    let even = factors.iter().position(|f| f % 2 == 0).unwrap();
+    factors[even] /= 2;
+    factors.iter().fold(1 % m, |acc, &f| acc * (f % m) % m) as i64
+}
@@ -0,0 +1,289 @@
+use std::hint::black_box;
+
+use crate::bench::parse_param;
+use crate::benches::loop_test::{loop_sum_closed_form, LoopTest};
+use crate::{Benchmark, Expected, ParamError, Value};
+
+/// The divisor `loop_const_mod` bakes in, the default `modulus`.
+pub const CONST_MODULUS: i64 = 100_000;
+
+/// How a loop variant differs from `loop`, which sums `i64`s in indexed
+/// loops and reduces with `%` by a runtime divisor.
+///
+/// Every variant computes the same sum; on cores without a hardware divider,
+/// such as the Cortex-A7, the `%` dominates and the variants show what it
+/// costs.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Variant {
+    /// `i32` arithmetic.
+    I32,
+    /// `u64` arithmetic.
+    U64,
+    /// `wrapping_add` and `wrapping_rem`.
+    Wrapping,
+    /// `checked_add` and `checked_rem`, with the overflow passed out.
+    Checked,
+    /// `checked_add` and `checked_rem` with `expect`, which panic on
+    /// overflow the way a debug build's `+` and `%` do.
+    Strict,
+    /// `%` by the constant [`CONST_MODULUS`], which compiles to a multiply.
+    ConstMod,
+    /// A subtraction when the sum reaches the modulus instead of `%`.
+    BranchMod,
+    /// `(1..outer).flat_map(..)` folded instead of nested `for` loops.
+    FlatMap,
+}
+
+/// The `loop` kernel written another way; see [`Variant`].
+#[derive(Debug, Clone, Copy)]
+pub struct LoopVariant {
+    pub variant: Variant,
+    pub outer: i64,
+    pub inner: i64,
+    pub modulus: i64,
+}
+
+impl LoopVariant {
+    pub fn new(variant: Variant) -> Self {
+        let loop_test = LoopTest::default();
+        LoopVariant {
+            variant,
+            outer: loop_test.outer,
+            inner: loop_test.inner,
+            modulus: loop_test.modulus,
+        }
+    }
+
+    /// Why the variant cannot run at these sizes, if it cannot: the `i32`
+    /// variant needs `sum + i + j` to fit in an `i32`, and the
+    /// compare-and-subtract variant needs `i + j` below the modulus.
+    fn unsupported(&self) -> Option<&'static str> {
+        match self.variant {
+            Variant::I32 => {
+                let (Ok(outer), Ok(inner), Ok(modulus)) = (
+                    i32::try_from(self.outer),
+                    i32::try_from(self.inner),
+                    i32::try_from(self.modulus),
+                ) else {
+                    return Some("modulus + outer + inner must fit in an i32");
+                };
+                // All three are at least 1.
+                (modulus - 1)
+                    .checked_add(outer - 1)
+                    .and_then(|s| s.checked_add(inner - 1))
+                    .is_none()
+                    .then_some("modulus + outer + inner must fit in an i32")
+            }
+            Variant::BranchMod => (self.modulus <= self.outer + self.inner - 2)
+                .then_some("modulus must be above outer + inner - 2"),
+            _ => None,
+        }
+    }
+}
+
+impl Benchmark for LoopVariant {
+    fn name(&self) -> &'static str {
+        match self.variant {
+            Variant::I32 => "loop_i32",
+            Variant::U64 => "loop_u64",
+            Variant::Wrapping => "loop_wrapping",
+            Variant::Checked => "loop_checked",
+            Variant::Strict => "loop_strict",
+            Variant::ConstMod => "loop_const_mod",
+            Variant::BranchMod => "loop_branch_mod",
+            Variant::FlatMap => "loop_flat_map",
+        }
+    }
+
+    fn description(&self) -> &'static str {
+        match self.variant {
+            Variant::I32 => "loop in i32 arithmetic",
+            Variant::U64 => "loop in u64 arithmetic",
+            Variant::Wrapping => "loop with wrapping_add and wrapping_rem",
+            Variant::Checked => "loop with checked_add and checked_rem",
+            Variant::Strict => "loop panicking on overflow, like a debug build",
+            Variant::ConstMod => "loop reducing modulo the constant 100000",
+            Variant::BranchMod => "loop reducing by a compare and subtract instead of %",
+            Variant::FlatMap => "loop as a flat_map iterator folded into the sum",
+        }
+    }
+
+    /// `loop_const_mod` has no `modulus`; its divisor is compiled in.
+    fn params(&self) -> Vec<(&'static str, String)> {
+        let mut params = vec![
+            ("outer", self.outer.to_string()),
+            ("inner", self.inner.to_string()),
+        ];
+        if self.variant != Variant::ConstMod {
+            params.push(("modulus", self.modulus.to_string()));
+        }
+        params
+    }
+
+    fn set_param(&mut self, key: &str, value: &str) -> Result<(), ParamError> {
+        let mut next = *self;
+        match key {
+            "outer" => next.outer = parse_param(key, value, 1)?,
+            "inner" => next.inner = parse_param(key, value, 1)?,
+            "modulus" if self.variant != Variant::ConstMod => {
+                next.modulus = parse_param(key, value, 1)?
+            }
+            _ => return Err(ParamError::Unknown(key.to_string())),
+        }
+        if let Some(reason) = next.unsupported() {
+            return Err(ParamError::Unsupported {
+                key: key.to_string(),
+                reason: reason.to_string(),
+            });
+        }
+        *self = next;
+        Ok(())
+    }
+
+    fn size_param(&self) -> &'static str {
+        "outer"
+    }
+
+    fn clone_box(&self) -> Box<dyn Benchmark> {
+        Box::new(*self)
+    }
+
+    /// The `i32` variant stops growing where the sum would overflow, and
+    /// the compare-and-subtract variant where `i + j` reaches the modulus.
+    fn scaled(&self, factor: u64) -> Box<dyn Benchmark> {
+        let factor = i64::try_from(factor).unwrap_or(i64::MAX);
+        let mut outer = (self.outer - 1).saturating_mul(factor).saturating_add(1);
+        match self.variant {
+            Variant::I32 => {
+                outer = outer.min(i32::MAX as i64 - (self.modulus - 1) - (self.inner - 1) + 1)
+            }
+            Variant::BranchMod => outer = outer.min(self.modulus - self.inner + 1),
+            _ => {}
+        }
+        Box::new(LoopVariant { outer, ..*self })
+    }
+
+    fn work(&self) -> f64 {
+        (self.outer - 1) as f64 * (self.inner - 1) as f64
+    }
+
+    fn reference(&self) -> Option<&'static str> {
+        Some("loop")
+    }
+
+    fn run(&self) -> Value {
+        let (outer, inner, modulus) = (
+            black_box(self.outer),
+            black_box(self.inner),
+            black_box(self.modulus),
+        );
+        let sum = match self.variant {
+            Variant::I32 => sum_i32(outer as i32, inner as i32, modulus as i32) as i64,
+            Variant::U64 => sum_u64(outer as u64, inner as u64, modulus as u64) as i64,
+            Variant::Wrapping => sum_wrapping(outer, inner, modulus),
+            // An overflow gives -1, which never verifies.
+            Variant::Checked => sum_checked(outer, inner, modulus).unwrap_or(-1),
+            Variant::Strict => sum_strict(outer, inner, modulus),
+            Variant::ConstMod => sum_const_mod(outer, inner),
+            Variant::BranchMod => sum_branch_mod(outer, inner, modulus),
+            Variant::FlatMap => sum_flat_map(outer, inner, modulus),
+        };
+        Value::Int(black_box(sum))
+    }
+
+    fn expected(&self) -> Expected {
+        Expected::Exact(Value::Int(loop_sum_closed_form(
+            self.outer,
+            self.inner,
+            self.modulus,
+        )))
+    }
+}
+
+pub fn sum_i32(outer: i32, inner: i32, modulus: i32) -> i32 {
+    let mut sum: i32 = 0;
+    for i in 1..outer {
+        for j in 1..inner {
+            sum = (sum + i + j) % modulus;
+        }
+    }
+    sum
+}
+
+pub fn sum_u64(outer: u64, inner: u64, modulus: u64) -> u64 {
+    let mut sum: u64 = 0;
+    for i in 1..outer {
+        for j in 1..inner {
+            sum = (sum + i + j) % modulus;
+        }
+    }
+    sum
+}
+
+pub fn sum_wrapping(outer: i64, inner: i64, modulus: i64) -> i64 {
+    let mut sum: i64 = 0;
+    for i in 1..outer {
+        for j in 1..inner {
+            sum = sum.wrapping_add(i).wrapping_add(j).wrapping_rem(modulus);
+        }
+    }
+    sum
+}
+
+pub fn sum_checked(outer: i64, inner: i64, modulus: i64) -> Option<i64> {
+    let mut sum: i64 = 0;
+    for i in 1..outer {
+        for j in 1..inner {
+            sum = sum.checked_add(i)?.checked_add(j)?.checked_rem(modulus)?;
+        }
+    }
+    Some(sum)
+}
+
+/// The checks of a debug build, written out. `strict_add` and `strict_rem`
+/// say the same but need Rust 1.91.
+pub fn sum_strict(outer: i64, inner: i64, modulus: i64) -> i64 {
+    let mut sum: i64 = 0;
+    for i in 1..outer {
+        for j in 1..inner {
+            sum = sum
+                .checked_add(i)
+                .and_then(|s| s.checked_add(j))
+                .expect("attempt to add with overflow")
+                .checked_rem(modulus)
+                .expect("attempt to calculate the remainder with a divisor of zero");
+        }
+    }
+    sum
+}
+
+pub fn sum_const_mod(outer: i64, inner: i64) -> i64 {
+    let mut sum: i64 = 0;
+    for i in 1..outer {
+        for j in 1..inner {
+            sum = (sum + i + j) % CONST_MODULUS;
+        }
+    }
+    sum
+}
+
+/// Reduces by subtracting. `loop_branch_mod` only takes sizes with `i + j`
+/// below the modulus, so the `while` runs at most once per step.
+pub fn sum_branch_mod(outer: i64, inner: i64, modulus: i64) -> i64 {
+    let mut sum: i64 = 0;
+    for i in 1..outer {
+        for j in 1..inner {
+            sum += i + j;
+            while sum >= modulus {
+                sum -= modulus;
+            }
+        }
+    }
+    sum
+}
+
+pub fn sum_flat_map(outer: i64, inner: i64, modulus: i64) -> i64 {
+    (1..outer)
+        .flat_map(|i| (1..inner).map(move |j| i + j))
+        .fold(0, |sum, x| (sum + x) % modulus)
+}
@@ -0,0 +1,110 @@
+//! The registered speed tests.
+
+use crate::Benchmark;
+
+pub mod call_overhead;
+pub mod fast_quad_res;
+pub mod fixed_point_pi;
+pub mod function_call;
+pub mod loop_test;
+pub mod loop_variants;
+pub mod monte_carlo_pi;
+pub mod qmc_pi;
+pub mod rng_draw;
+pub mod simd_pi;
+
+/// Which language's kernel a test runs. The C kernels are the original
+/// programs' own, linked in through [`crate::ffi`]; the Python ones run in
+/// an embedded interpreter, see [`crate::python`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum Kernel {
+    #[default]
+    Rust,
+    C,
+    #[cfg(feature = "python")]
+    Python,
+}
+
+/// Every benchmark the runner knows about, in the order they are run.
+pub fn registry() -> Vec<Box<dyn Benchmark>> {
+    #[allow(unused_mut)]
+    let mut registry: Vec<Box<dyn Benchmark>> = vec![
+        Box::new(loop_test::LoopTest::default()),
+        Box::new(loop_test::LoopTest::with_kernel(Kernel::C)),
+        Box::new(loop_variants::LoopVariant::new(loop_variants::Variant::I32)),
+        Box::new(loop_variants::LoopVariant::new(loop_variants::Variant::U64)),
+        Box::new(loop_variants::LoopVariant::new(
+            loop_variants::Variant::Wrapping,
+        )),
+        Box::new(loop_variants::LoopVariant::new(
+            loop_variants::Variant::Checked,
+        )),
+        Box::new(loop_variants::LoopVariant::new(
+            loop_variants::Variant::Strict,
+        )),
+        Box::new(loop_variants::LoopVariant::new(
+            loop_variants::Variant::ConstMod,
+        )),
+        Box::new(loop_variants::LoopVariant::new(
+            loop_variants::Variant::BranchMod,
+        )),
+        Box::new(loop_variants::LoopVariant::new(
+            loop_variants::Variant::FlatMap,
+        )),
+        Box::new(function_call::FunctionCall::default()),
+        Box::new(function_call::FunctionCall::with_kernel(Kernel::C)),
+        Box::new(call_overhead::CallOverhead::new(
+            call_overhead::Call::Inline,
+        )),
+        Box::new(call_overhead::CallOverhead::new(
+            call_overhead::Call::NeverInline,
+        )),
+        Box::new(call_overhead::CallOverhead::new(
+            call_overhead::Call::FnPointer,
+        )),
+        Box::new(call_overhead::CallOverhead::new(call_overhead::Call::Dyn)),
+        Box::new(call_overhead::CallOverhead::new(
+            call_overhead::Call::Closure,
+        )),
+        Box::new(call_overhead::CallOverhead::new(call_overhead::Call::Fib)),
+        Box::new(call_overhead::CallOverhead::new(
+            call_overhead::Call::Ackermann,
+        )),
+        Box::new(call_overhead::CallOverhead::new(
+            call_overhead::Call::QuadRes,
+        )),
+        Box::new(call_overhead::CallOverhead::new(call_overhead::Call::Ffi)),
+        Box::new(fast_quad_res::FastQuadRes::new(
+            fast_quad_res::Method::Sieve,
+        )),
+        Box::new(fast_quad_res::FastQuadRes::new(
+            fast_quad_res::Method::Euler,
+        )),
+        Box::new(fast_quad_res::FastQuadRes::new(
+            fast_quad_res::Method::Jacobi,
+        )),
+        Box::new(fast_quad_res::FastQuadRes::new(fast_quad_res::Method::Crt)),
+        Box::new(monte_carlo_pi::MonteCarloPi::default()),
+        Box::new(monte_carlo_pi::MonteCarloPi::with_kernel(Kernel::C)),
+        Box::new(monte_carlo_pi::ParallelMonteCarloPi::default()),
+        Box::new(simd_pi::SimdPi::default()),
+        Box::new(fixed_point_pi::ArithPi::new(fixed_point_pi::Arith::Fixed)),
+        Box::new(fixed_point_pi::ArithPi::new(fixed_point_pi::Arith::F32)),
+        Box::new(fixed_point_pi::ArithPi::new(fixed_point_pi::Arith::F64)),
+        Box::new(qmc_pi::QmcPi::default()),
+        Box::new(rng_draw::RngDraw::default()),
+    ];
+    // The interpreted kernels take seconds, so they come last.
+    #[cfg(feature = "python")]
+    registry.extend::<[Box<dyn Benchmark>; 3]>([
+        Box::new(loop_test::LoopTest::with_kernel(Kernel::Python)),
+        Box::new(function_call::FunctionCall::with_kernel(Kernel::Python)),
+        Box::new(monte_carlo_pi::MonteCarloPi::with_kernel(Kernel::Python)),
+    ]);
+    registry
+}
+
+/// Looks up a benchmark by name or alias.
+pub fn find(name: &str) -> Option<Box<dyn Benchmark>> {
+    registry().into_iter().find(|b| b.matches(name))
+}
@@ -0,0 +1,344 @@
+use std::hint::black_box;
+use std::thread;
+
+use crate::bench::parse_param;
+use crate::benches::Kernel;
+use crate::ffi::c_count_inside_circle;
+#[cfg(feature = "python")]
+use crate::python::py_count_inside_circle;
+use crate::rng::{Backend, RngFn, UnitRng};
+use crate::{Benchmark, Expected, ParamError, Uncertainty, Value};
+
+/// Estimates pi from random points in the unit square, the same as
+/// `monte_carlo_pi.c/.py`.
+#[derive(Debug, Clone, Copy)]
+pub struct MonteCarloPi {
+    pub kernel: Kernel,
+    pub iterations: u64,
+    /// Unused by the C kernel, which draws from the C library's `rand()`
+    /// seeded with the low 32 bits of `seed`, and the Python kernel, which
+    /// draws from `random.random()` after `random.seed(seed)`.
+    pub rng: Backend,
+    /// Every run draws from stream 0 of this seed, so a recorded seed
+    /// reproduces the estimate exactly (except with [`Backend::Thread`],
+    /// which cannot be seeded).
+    pub seed: u64,
+}
+
+impl Default for MonteCarloPi {
+    fn default() -> Self {
+        MonteCarloPi {
+            kernel: Kernel::Rust,
+            iterations: 10_000_000,
+            rng: Backend::ChaCha12,
+            seed: rand::random(),
+        }
+    }
+}
+
+impl MonteCarloPi {
+    pub fn with_kernel(kernel: Kernel) -> Self {
+        MonteCarloPi {
+            kernel,
+            ..MonteCarloPi::default()
+        }
+    }
+}
+
+impl Benchmark for MonteCarloPi {
+    fn name(&self) -> &'static str {
+        match self.kernel {
+            Kernel::Rust => "monte_carlo_pi",
+            Kernel::C => "monte_carlo_pi_c",
+            #[cfg(feature = "python")]
+            Kernel::Python => "monte_carlo_pi_py",
+        }
+    }
+
+    fn description(&self) -> &'static str {
+        match self.kernel {
+            Kernel::Rust => "estimate pi from `iterations` random points in the unit square",
+            Kernel::C => {
+                "monte_carlo_pi with the kernel and rand() of monte_carlo_pi.c, through FFI"
+            }
+            #[cfg(feature = "python")]
+            Kernel::Python => {
+                "monte_carlo_pi with the kernel of monte_carlo_pi.py, in embedded CPython"
+            }
+        }
+    }
+
+    fn params(&self) -> Vec<(&'static str, String)> {
+        let mut params = vec![("iterations", self.iterations.to_string())];
+        if self.kernel == Kernel::Rust {
+            params.push(("rng", self.rng.to_string()));
+        }
+        params.push(("seed", self.seed.to_string()));
+        params
+    }
+
+    fn set_param(&mut self, key: &str, value: &str) -> Result<(), ParamError> {
+        match key {
+            "iterations" => self.iterations = parse_param(key, value, 1)?,
+            "rng" if self.kernel == Kernel::Rust => self.rng = parse_backend(key, value)?,
+            "seed" => self.seed = parse_param(key, value, 0)?,
+            _ => return Err(ParamError::Unknown(key.to_string())),
+        }
+        Ok(())
+    }
+
+    fn size_param(&self) -> &'static str {
+        "iterations"
+    }
+
+    fn clone_box(&self) -> Box<dyn Benchmark> {
+        Box::new(*self)
+    }
+
+    fn scaled(&self, factor: u64) -> Box<dyn Benchmark> {
+        Box::new(MonteCarloPi {
+            iterations: self.iterations * factor,
+            ..*self
+        })
+    }
+
+    fn work(&self) -> f64 {
+        self.iterations as f64
+    }
+
+    fn run(&self) -> Value {
+        let iterations = black_box(self.iterations);
+        let seed = black_box(self.seed);
+        let inside = match self.kernel {
+            Kernel::Rust => self.rng.with_rng(seed, 0, CountInside { iterations }),
+            Kernel::C => c_count_inside_circle(seed as u32, iterations),
+            #[cfg(feature = "python")]
+            Kernel::Python => py_count_inside_circle(seed, iterations),
+        };
+        Value::Float(black_box(pi_from_count(inside, iterations)))
+    }
+
+    /// glibc's `rand()` and Python's `random()` are reproduced by
+    /// [`Backend::Glibc`] and [`Backend::Mt19937`], so the C kernel on glibc
+    /// and the Python kernel must match the Rust one to the bit.
+    fn expected(&self) -> Expected {
+        let same_points = match self.kernel {
+            Kernel::C if cfg!(all(target_os = "linux", target_env = "gnu")) => Some(Backend::Glibc),
+            #[cfg(feature = "python")]
+            Kernel::Python => Some(Backend::Mt19937),
+            _ => None,
+        };
+        if let Some(rng) = same_points {
+            let rust = MonteCarloPi {
+                kernel: Kernel::Rust,
+                rng,
+                ..*self
+            };
+            return Expected::Exact(rust.run());
+        }
+        Expected::Within {
+            target: std::f64::consts::PI,
+            tolerance: 6.0 * standard_error(self.iterations),
+        }
+    }
+
+    fn uncertainty(&self, value: Value) -> Option<Uncertainty> {
+        pi_uncertainty(value, self.iterations)
+    }
+}
+
+/// The same estimate split across `threads` threads.
+#[derive(Debug, Clone, Copy)]
+pub struct ParallelMonteCarloPi {
+    pub iterations: u64,
+    pub threads: u64,
+    /// Base seed. The samples are cut into chunks of [`CHUNK`] and chunk `c`
+    /// draws from stream `c` of this seed, so the estimate depends only on
+    /// the seed and generator, not on the thread count.
+    pub seed: u64,
+    pub rng: Backend,
+}
+
+impl Default for ParallelMonteCarloPi {
+    fn default() -> Self {
+        ParallelMonteCarloPi {
+            iterations: 10_000_000,
+            threads: thread::available_parallelism().map_or(1, |n| n.get() as u64),
+            seed: rand::random(),
+            rng: Backend::ChaCha8,
+        }
+    }
+}
+
+impl Benchmark for ParallelMonteCarloPi {
+    fn name(&self) -> &'static str {
+        "monte_carlo_pi_mt"
+    }
+
+    fn description(&self) -> &'static str {
+        "monte_carlo_pi split across `threads` threads with per-thread RNG streams"
+    }
+
+    fn params(&self) -> Vec<(&'static str, String)> {
+        vec![
+            ("iterations", self.iterations.to_string()),
+            ("threads", self.threads.to_string()),
+            ("seed", self.seed.to_string()),
+            ("rng", self.rng.to_string()),
+        ]
+    }
+
+    fn set_param(&mut self, key: &str, value: &str) -> Result<(), ParamError> {
+        match key {
+            "iterations" => self.iterations = parse_param(key, value, 1)?,
+            "threads" => self.threads = parse_param(key, value, 1)?,
+            "seed" => self.seed = parse_param(key, value, 0)?,
+            "rng" => self.rng = parse_backend(key, value)?,
+            _ => return Err(ParamError::Unknown(key.to_string())),
+        }
+        Ok(())
+    }
+
+    fn size_param(&self) -> &'static str {
+        "iterations"
+    }
+
+    fn clone_box(&self) -> Box<dyn Benchmark> {
+        Box::new(*self)
+    }
+
+    fn scaled(&self, factor: u64) -> Box<dyn Benchmark> {
+        Box::new(ParallelMonteCarloPi {
+            iterations: self.iterations * factor,
+            ..*self
+        })
+    }
+
+    fn work(&self) -> f64 {
+        self.iterations as f64
+    }
+
+    fn run(&self) -> Value {
+        let pi = estimate_pi_parallel(
+            self.rng,
+            black_box(self.iterations),
+            black_box(self.threads),
+            black_box(self.seed),
+        );
+        Value::Float(black_box(pi))
+    }
+
+    fn expected(&self) -> Expected {
+        Expected::Within {
+            target: std::f64::consts::PI,
+            tolerance: 6.0 * standard_error(self.iterations),
+        }
+    }
+
+    fn uncertainty(&self, value: Value) -> Option<Uncertainty> {
+        pi_uncertainty(value, self.iterations)
+    }
+}
+
+pub(crate) fn parse_backend(key: &str, value: &str) -> Result<Backend, ParamError> {
+    value.parse().map_err(|reason| ParamError::Invalid {
+        key: key.to_string(),
+        reason,
+    })
+}
+
+pub fn estimate_pi<R: UnitRng>(rng: &mut R, iterations: u64) -> f64 {
+    pi_from_count(count_inside(rng, iterations), iterations)
+}
+
+pub fn pi_from_count(inside: u64, iterations: u64) -> f64 {
+    if iterations == 0 {
+        return 0.0;
+    }
+    4.0 * (inside as f64) / (iterations as f64)
+}
+
+/// How many of `iterations` random points fall inside the quarter circle.
+pub fn count_inside<R: UnitRng>(rng: &mut R, iterations: u64) -> u64 {
+    let mut inside: u64 = 0;
+    for _ in 0..iterations {
+        let x = rng.next_f64();
+        let y = rng.next_f64();
+        if x * x + y * y <= 1.0 {
+            inside += 1;
+        }
+    }
+    inside
+}
+
+/// [`count_inside`] as an [`RngFn`], for [`Backend::with_rng`].
+pub struct CountInside {
+    pub iterations: u64,
+}
+
+impl RngFn<u64> for CountInside {
+    fn call<R: UnitRng>(self, rng: &mut R) -> u64 {
+        count_inside(rng, self.iterations)
+    }
+}
+
+/// Samples per RNG stream in [`estimate_pi_parallel`].
+pub const CHUNK: u64 = 1 << 20;
+
+/// Cuts `iterations` into chunks of [`CHUNK`] samples, chunk `c` drawing from
+/// stream `c` of `seed`, and deals the chunks round-robin to `threads`
+/// threads. Since the counts are integers the total is the same however
+/// the chunks are dealt, so any thread count gives a bit-identical result.
+pub fn estimate_pi_parallel(rng: Backend, iterations: u64, threads: u64, seed: u64) -> f64 {
+    if iterations == 0 {
+        return 0.0;
+    }
+    let chunks = iterations.div_ceil(CHUNK);
+    let threads = threads.clamp(1, chunks);
+    let inside: u64 = thread::scope(|s| {
+        let handles: Vec<_> = (0..threads)
+            .map(|k| {
+                s.spawn(move || {
+                    (k..chunks)
+                        .step_by(threads as usize)
+                        .map(|c| {
+                            let share = CHUNK.min(iterations - c * CHUNK);
+                            rng.with_rng(seed, c, CountInside { iterations: share })
+                        })
+                        .sum::<u64>()
+                })
+            })
+            .collect();
+        handles
+            .into_iter()
+            .map(|h| h.join().expect("worker thread panicked"))
+            .sum()
+    });
+    pi_from_count(inside, iterations)
+}
+
+/// The binomial standard error and confidence interval of an estimate
+/// `value` from `iterations` points, using the observed hit rate.
+pub(crate) fn pi_uncertainty(value: Value, iterations: u64) -> Option<Uncertainty> {
+    match value {
+        Value::Float(pi) => Some(Uncertainty::normal(
+            pi,
+            observed_standard_error(pi, iterations),
+        )),
+        Value::Int(_) => None,
+    }
+}
+
+/// Standard error of a pi estimate `pi` from `iterations` points, with the
+/// hit probability estimated as `pi / 4`.
+pub fn observed_standard_error(pi: f64, iterations: u64) -> f64 {
+    let p = (pi / 4.0).clamp(0.0, 1.0);
+    4.0 * (p * (1.0 - p) / iterations as f64).sqrt()
+}
+
+/// Standard error of a pi estimate from `iterations` points: each point lands
+/// inside with probability pi/4, and the estimate is 4 times the hit rate.
+pub fn standard_error(iterations: u64) -> f64 {
+    let p = std::f64::consts::FRAC_PI_4;
+    4.0 * (p * (1.0 - p) / iterations as f64).sqrt()
+}
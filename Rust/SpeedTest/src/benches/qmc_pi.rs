@@ -0,0 +1,134 @@
+use std::hint::black_box;
+
+use crate::bench::parse_param;
+use crate::benches::monte_carlo_pi::{pi_from_count, standard_error};
+use crate::qmc::{self, count_inside_points, PointFn, PointSet, Qmc, SOBOL_POINTS};
+use crate::{Benchmark, Expected, ParamError, Value};
+
+/// The pi estimate of `monte_carlo_pi` with low-discrepancy points in place
+/// of pseudo-random ones.
+#[derive(Debug, Clone, Copy)]
+pub struct QmcPi {
+    pub iterations: u64,
+    pub qmc: Qmc,
+    /// Seeds the Owen scramble; unused when `scramble=none`.
+    pub seed: u64,
+}
+
+impl Default for QmcPi {
+    fn default() -> Self {
+        QmcPi {
+            iterations: 10_000_000,
+            qmc: Qmc::default(),
+            seed: rand::random(),
+        }
+    }
+}
+
+impl QmcPi {
+    /// Sobol runs out of distinct points at 2^32.
+    fn check_length(&self) -> Result<(), ParamError> {
+        if self.qmc.sequence == qmc::Sequence::Sobol && self.iterations > SOBOL_POINTS {
+            return Err(ParamError::Invalid {
+                key: "iterations".to_string(),
+                reason: format!("sobol has only {} points", SOBOL_POINTS),
+            });
+        }
+        Ok(())
+    }
+}
+
+impl Benchmark for QmcPi {
+    fn name(&self) -> &'static str {
+        "qmc_pi"
+    }
+
+    fn description(&self) -> &'static str {
+        "estimate pi from `iterations` Halton or Sobol points (quasi-Monte Carlo)"
+    }
+
+    fn params(&self) -> Vec<(&'static str, String)> {
+        vec![
+            ("iterations", self.iterations.to_string()),
+            ("sequence", self.qmc.sequence.to_string()),
+            ("bases", qmc::format_bases(self.qmc.bases)),
+            (
+                "scramble",
+                if self.qmc.scramble { "owen" } else { "none" }.to_string(),
+            ),
+            ("seed", self.seed.to_string()),
+        ]
+    }
+
+    fn set_param(&mut self, key: &str, value: &str) -> Result<(), ParamError> {
+        let invalid = |reason: String| ParamError::Invalid {
+            key: key.to_string(),
+            reason,
+        };
+        let mut next = *self;
+        match key {
+            "iterations" => next.iterations = parse_param(key, value, 1)?,
+            "sequence" => next.qmc.sequence = value.parse().map_err(invalid)?,
+            "bases" => next.qmc.bases = qmc::parse_bases(value).map_err(invalid)?,
+            "scramble" => {
+                next.qmc.scramble = match value {
+                    "owen" => true,
+                    "none" => false,
+                    _ => return Err(invalid("expected `owen` or `none`".to_string())),
+                }
+            }
+            "seed" => next.seed = parse_param(key, value, 0)?,
+            _ => return Err(ParamError::Unknown(key.to_string())),
+        }
+        next.check_length()?;
+        *self = next;
+        Ok(())
+    }
+
+    fn size_param(&self) -> &'static str {
+        "iterations"
+    }
+
+    fn clone_box(&self) -> Box<dyn Benchmark> {
+        Box::new(*self)
+    }
+
+    fn scaled(&self, factor: u64) -> Box<dyn Benchmark> {
+        Box::new(QmcPi {
+            iterations: self.iterations * factor,
+            ..*self
+        })
+    }
+
+    fn work(&self) -> f64 {
+        self.iterations as f64
+    }
+
+    fn run(&self) -> Value {
+        let iterations = black_box(self.iterations);
+        let inside = self
+            .qmc
+            .with_points(black_box(self.seed), CountInsidePoints { iterations });
+        Value::Float(black_box(pi_from_count(inside, iterations)))
+    }
+
+    /// Quasi-Monte Carlo beats pseudo-random sampling, so the pseudo-random
+    /// tolerance is a loose but safe bound.
+    fn expected(&self) -> Expected {
+        Expected::Within {
+            target: std::f64::consts::PI,
+            tolerance: 6.0 * standard_error(self.iterations),
+        }
+    }
+}
+
+/// [`count_inside_points`] as a [`PointFn`], for [`Qmc::with_points`].
+pub struct CountInsidePoints {
+    pub iterations: u64,
+}
+
+impl PointFn<u64> for CountInsidePoints {
+    fn call<P: PointSet>(self, points: &mut P) -> u64 {
+        count_inside_points(points, self.iterations)
+    }
+}
@@ -0,0 +1,107 @@
+use std::hint::black_box;
+
+use crate::bench::parse_param;
+use crate::rng::{Backend, RngFn, UnitRng};
+use crate::{Benchmark, Expected, ParamError, Value};
+
+/// Draws the same `2 * iterations` doubles as `monte_carlo_pi` but only sums
+/// them, so its time is the generator's share of the pi benchmark.
+#[derive(Debug, Clone, Copy)]
+pub struct RngDraw {
+    pub iterations: u64,
+    pub rng: Backend,
+    pub seed: u64,
+}
+
+impl Default for RngDraw {
+    fn default() -> Self {
+        RngDraw {
+            iterations: 10_000_000,
+            rng: Backend::ChaCha12,
+            seed: rand::random(),
+        }
+    }
+}
+
+impl Benchmark for RngDraw {
+    fn name(&self) -> &'static str {
+        "rng_draw"
+    }
+
+    fn description(&self) -> &'static str {
+        "draw 2 x `iterations` doubles from `rng` and average them (RNG cost alone)"
+    }
+
+    fn params(&self) -> Vec<(&'static str, String)> {
+        vec![
+            ("iterations", self.iterations.to_string()),
+            ("rng", self.rng.to_string()),
+            ("seed", self.seed.to_string()),
+        ]
+    }
+
+    fn set_param(&mut self, key: &str, value: &str) -> Result<(), ParamError> {
+        match key {
+            "iterations" => self.iterations = parse_param(key, value, 1)?,
+            "rng" => {
+                self.rng = value.parse().map_err(|reason| ParamError::Invalid {
+                    key: key.to_string(),
+                    reason,
+                })?
+            }
+            "seed" => self.seed = parse_param(key, value, 0)?,
+            _ => return Err(ParamError::Unknown(key.to_string())),
+        }
+        Ok(())
+    }
+
+    fn size_param(&self) -> &'static str {
+        "iterations"
+    }
+
+    fn clone_box(&self) -> Box<dyn Benchmark> {
+        Box::new(*self)
+    }
+
+    fn scaled(&self, factor: u64) -> Box<dyn Benchmark> {
+        Box::new(RngDraw {
+            iterations: self.iterations * factor,
+            ..*self
+        })
+    }
+
+    fn work(&self) -> f64 {
+        self.iterations as f64
+    }
+
+    fn run(&self) -> Value {
+        let draws = 2 * black_box(self.iterations);
+        let sum = self
+            .rng
+            .with_rng(black_box(self.seed), 0, SumDraws { draws });
+        Value::Float(black_box(sum / draws as f64))
+    }
+
+    /// The mean of `n` uniform draws has standard deviation `sqrt(1/12n)`.
+    fn expected(&self) -> Expected {
+        let draws = 2.0 * self.iterations as f64;
+        Expected::Within {
+            target: 0.5,
+            tolerance: 6.0 * (1.0 / (12.0 * draws)).sqrt(),
+        }
+    }
+}
+
+struct SumDraws {
+    draws: u64,
+}
+
+impl RngFn<f64> for SumDraws {
+    fn call<R: UnitRng>(self, rng: &mut R) -> f64 {
+        let mut sum = 0.0;
+        for _ in 0..self.draws {
+            sum += rng.next_f64();
+        }
+        sum
+    }
+}
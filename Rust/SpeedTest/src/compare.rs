@@ -0,0 +1,263 @@
+//! Runs the C, Python and Rust versions of each test side by side.
+//!
+//! The C files are compiled into a build directory, the Python files are run
+//! with the chosen interpreter, and the Rust kernels run in-process. Each
+//! program reports its own kernel time in milliseconds, so process startup
+//! is left out of all three.
+
+use std::fmt;
+use std::path::{Path, PathBuf};
+use std::process::Command;
+
+use crate::stats::median_of_sorted;
+use crate::{bench, Benchmark, RunConfig, Value};
+
+/// How to build and run the non-Rust variants.
+#[derive(Debug, Clone)]
+pub struct CompareConfig {
+    /// Directory holding the `*.c` and `*.py` sources.
+    pub source_dir: PathBuf,
+    /// Where compiled C binaries are written.
+    pub build_dir: PathBuf,
+    pub cc: String,
+    pub cflags: Vec<String>,
+    pub python: String,
+    /// Timed runs per language; the median is reported.
+    pub runs: usize,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Lang {
+    C,
+    Python,
+    Rust,
+}
+
+impl fmt::Display for Lang {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            Lang::C => "C",
+            Lang::Python => "Python",
+            Lang::Rust => "Rust",
+        })
+    }
+}
+
+/// What one language's variant of a test produced.
+#[derive(Debug, Clone, Copy)]
+pub struct LangResult {
+    pub value: Value,
+    /// Median of the self-reported kernel times.
+    pub ms: f64,
+}
+
+/// One test compared across the three languages.
+#[derive(Debug)]
+pub struct Row {
+    pub benchmark: &'static str,
+    pub c: Result<LangResult, String>,
+    pub python: Result<LangResult, String>,
+    pub rust: LangResult,
+    /// Set when a C or Python answer disagrees with the Rust one.
+    pub mismatch: Option<String>,
+}
+
+impl Row {
+    pub fn get(&self, lang: Lang) -> Option<&LangResult> {
+        match lang {
+            Lang::C => self.c.as_ref().ok(),
+            Lang::Python => self.python.as_ref().ok(),
+            Lang::Rust => Some(&self.rust),
+        }
+    }
+
+    /// How many times faster `lang` ran than Python.
+    pub fn speedup(&self, lang: Lang) -> Option<f64> {
+        let python = self.get(Lang::Python)?.ms;
+        let other = self.get(lang)?.ms;
+        (other > 0.0).then(|| python / other)
+    }
+}
+
+/// The C/Python file stem for a Rust benchmark, or `None` if it has no
+/// counterpart.
+pub fn source_stem(benchmark: &str) -> Option<&'static str> {
+    match benchmark {
+        "loop" => Some("loop_test"),
+        "function_call" => Some("function_call"),
+        "monte_carlo_pi" => Some("monte_carlo_pi"),
+        _ => None,
+    }
+}
+
+/// Runs one benchmark in all three languages.
+pub fn compare(bench: &dyn Benchmark, config: &CompareConfig) -> Result<Row, String> {
+    let stem = source_stem(bench.name())
+        .ok_or_else(|| format!("`{}` has no C or Python version", bench.name()))?;
+
+    let c = build_c(stem, config).and_then(|exe| run_external(Command::new(exe), config.runs));
+    let python = {
+        let script = config.source_dir.join(format!("{}.py", stem));
+        let mut cmd = Command::new(&config.python);
+        cmd.arg(script);
+        run_external(cmd, config.runs)
+    };
+    let m = bench::measure(
+        bench,
+        &RunConfig {
+            warmup: 1,
+            runs: config.runs,
+            counters: false,
+            memory: false,
+        },
+    );
+    let rust = LangResult {
+        value: m.value,
+        ms: m.summary.median / 1e6,
+    };
+
+    let mut mismatches = Vec::new();
+    for (lang, other) in [(Lang::C, &c), (Lang::Python, &python)] {
+        if let Ok(other) = other {
+            if !agrees(bench.name(), rust.value, other.value) {
+                mismatches.push(format!(
+                    "{} gave {} but Rust gave {}",
+                    lang, other.value, rust.value
+                ));
+            }
+        }
+    }
+    Ok(Row {
+        benchmark: bench.name(),
+        c,
+        python,
+        rust,
+        mismatch: (!mismatches.is_empty()).then(|| mismatches.join("; ")),
+    })
+}
+
+/// Whether two languages' answers count as the same. Integer answers must be
+/// equal; the pi estimates come from different generators and only need to
+/// agree within about ten standard errors of a 10M-sample run.
+fn agrees(benchmark: &str, a: Value, b: Value) -> bool {
+    match (a, b) {
+        (Value::Int(a), Value::Int(b)) => a == b,
+        (a, b) => {
+            let tolerance = if benchmark == "monte_carlo_pi" {
+                5e-3
+            } else {
+                1e-9
+            };
+            (as_f64(a) - as_f64(b)).abs() <= tolerance
+        }
+    }
+}
+
+fn as_f64(v: Value) -> f64 {
+    match v {
+        Value::Int(v) => v as f64,
+        Value::Float(v) => v,
+    }
+}
+
+fn build_c(stem: &str, config: &CompareConfig) -> Result<PathBuf, String> {
+    std::fs::create_dir_all(&config.build_dir)
+        .map_err(|e| format!("creating {}: {}", config.build_dir.display(), e))?;
+    let source = config.source_dir.join(format!("{}.c", stem));
+    let exe = config.build_dir.join(stem);
+    let out = Command::new(&config.cc)
+        .args(&config.cflags)
+        .arg("-o")
+        .arg(&exe)
+        .arg(&source)
+        .output()
+        .map_err(|e| format!("running {}: {}", config.cc, e))?;
+    if !out.status.success() {
+        return Err(format!(
+            "{} failed on {}: {}",
+            config.cc,
+            source.display(),
+            String::from_utf8_lossy(&out.stderr).trim()
+        ));
+    }
+    Ok(exe)
+}
+
+/// Runs an external test program `runs` times and takes the median of the
+/// times it reports.
+fn run_external(mut cmd: Command, runs: usize) -> Result<LangResult, String> {
+    let mut times = Vec::with_capacity(runs);
+    let mut value = None;
+    for _ in 0..runs {
+        let out = cmd
+            .output()
+            .map_err(|e| format!("running {}: {}", display_program(&cmd), e))?;
+        if !out.status.success() {
+            return Err(format!(
+                "{} exited with {}",
+                display_program(&cmd),
+                out.status
+            ));
+        }
+        let parsed = parse_output(&String::from_utf8_lossy(&out.stdout))
+            .ok_or_else(|| format!("could not parse output of {}", display_program(&cmd)))?;
+        value = Some(parsed.value);
+        times.push(parsed.ms);
+    }
+    times.sort_by(f64::total_cmp);
+    Ok(LangResult {
+        value: value.ok_or("no runs")?,
+        ms: median_of_sorted(&times),
+    })
+}
+
+fn display_program(cmd: &Command) -> String {
+    let mut s = cmd.get_program().to_string_lossy().into_owned();
+    for arg in cmd.get_args() {
+        s.push(' ');
+        s.push_str(&arg.to_string_lossy());
+    }
+    s
+}
+
+/// Pulls the answer and the millisecond timing out of a test program's
+/// output. The answer is the first number following a `:` or `≈` on a line
+/// that is not a timing line, e.g. `Final sum: 1000` or `pi ≈ 3.141592`; the
+/// time is the `Time taken...: X ms` line.
+pub fn parse_output(stdout: &str) -> Option<LangResult> {
+    let mut value = None;
+    let mut ms = None;
+    for line in stdout.lines().map(str::trim) {
+        if line.starts_with("Time taken") {
+            if let Some(t) = line.strip_suffix(" ms") {
+                ms = after_separator(t).and_then(|s| s.parse::<f64>().ok());
+            }
+        } else if value.is_none() {
+            value = after_separator(line).and_then(parse_value);
+        }
+    }
+    Some(LangResult {
+        value: value?,
+        ms: ms?,
+    })
+}
+
+fn after_separator(line: &str) -> Option<&str> {
+    let i = line.rfind([':', '≈'])?;
+    let sep_len = line[i..].chars().next()?.len_utf8();
+    Some(line[i + sep_len..].trim())
+}
+
+fn parse_value(s: &str) -> Option<Value> {
+    if let Ok(v) = s.parse::<i64>() {
+        Some(Value::Int(v))
+    } else {
+        s.parse::<f64>().ok().map(Value::Float)
+    }
+}
+
+/// Default source directory: the crate root, where the `.c` and `.py` files
+/// live.
+pub fn default_source_dir() -> PathBuf {
+    Path::new(env!("CARGO_MANIFEST_DIR")).to_path_buf()
+}
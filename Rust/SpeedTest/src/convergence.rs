@@ -0,0 +1,158 @@
+//! How the Monte Carlo pi estimate converges as samples accumulate.
+//!
+//! A trace follows a single run of the pi kernel and records the estimate at
+//! logarithmically spaced sample counts. Plotted on log-log axes the absolute
+//! error of pseudo-random points scatters around the standard error, a line
+//! of slope -1/2; low-discrepancy points from [`crate::qmc`] fall closer to
+//! slope -1. Tracing several sources at the same counts compares them.
+
+use std::fmt;
+use std::io::{self, Write};
+
+use crate::benches::monte_carlo_pi::{count_inside, observed_standard_error, pi_from_count};
+use crate::qmc::{count_inside_points, PointFn, PointSet, Qmc};
+use crate::rng::{Backend, RngFn, UnitRng};
+use crate::sweep::fit_power_law;
+
+/// Where a trace's points come from.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Source {
+    /// Pseudo-random points, as in `monte_carlo_pi`.
+    Random(Backend),
+    /// Low-discrepancy points, as in `qmc_pi`.
+    Qmc(Qmc),
+}
+
+impl fmt::Display for Source {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Source::Random(rng) => write!(f, "{}", rng),
+            Source::Qmc(qmc) => write!(f, "{}", qmc),
+        }
+    }
+}
+
+/// The estimate after the first `n` samples.
+#[derive(Debug, Clone, Copy)]
+pub struct TracePoint {
+    pub n: u64,
+    pub inside: u64,
+    pub estimate: f64,
+    /// `|estimate - pi|`.
+    pub abs_error: f64,
+    /// Binomial standard error of the estimate. For low-discrepancy points
+    /// this is what pseudo-random points would give, not the actual error.
+    pub std_error: f64,
+}
+
+#[derive(Debug, Clone)]
+pub struct Trace {
+    pub source: Source,
+    pub seed: u64,
+    pub points: Vec<TracePoint>,
+    /// Fitted exponent of the absolute error against `n`; -0.5 is the
+    /// Monte Carlo ideal.
+    pub exponent: f64,
+}
+
+/// Sample counts `10^(k / per_decade)` for `k = 0, 1, ...`, rounded and
+/// deduplicated, up to and including `max`.
+pub fn checkpoints(max: u64, per_decade: u32) -> Vec<u64> {
+    let per_decade = per_decade.max(1) as f64;
+    let mut counts: Vec<u64> = (0..)
+        .map(|k| 10f64.powf(k as f64 / per_decade).round() as u64)
+        .take_while(|&n| n < max)
+        .collect();
+    counts.dedup();
+    counts.push(max.max(1));
+    counts
+}
+
+/// Runs the pi kernel for `iterations` samples from `source` and records the
+/// estimate at each of [`checkpoints`]. The last point is the same estimate
+/// `monte_carlo_pi` or `qmc_pi` returns for that source and seed.
+pub fn trace(source: Source, seed: u64, iterations: u64, per_decade: u32) -> Trace {
+    let counts = checkpoints(iterations, per_decade);
+    let count_at = CountAt { counts: &counts };
+    let inside = match source {
+        Source::Random(rng) => rng.with_rng(seed, 0, count_at),
+        Source::Qmc(qmc) => qmc.with_points(seed, count_at),
+    };
+    let points: Vec<TracePoint> = counts
+        .iter()
+        .zip(inside)
+        .map(|(&n, inside)| {
+            let estimate = pi_from_count(inside, n);
+            TracePoint {
+                n,
+                inside,
+                estimate,
+                abs_error: (estimate - std::f64::consts::PI).abs(),
+                std_error: observed_standard_error(estimate, n),
+            }
+        })
+        .collect();
+    // A lucky hit of pi exactly has no logarithm; leave it out of the fit.
+    let (ns, errors): (Vec<f64>, Vec<f64>) = points
+        .iter()
+        .filter(|p| p.abs_error > 0.0)
+        .map(|p| (p.n as f64, p.abs_error))
+        .unzip();
+    let (exponent, _) = fit_power_law(&ns, &errors);
+    Trace {
+        source,
+        seed,
+        points,
+        exponent,
+    }
+}
+
+/// Running inside-counts at each of an ascending list of sample counts.
+struct CountAt<'a> {
+    counts: &'a [u64],
+}
+
+impl RngFn<Vec<u64>> for CountAt<'_> {
+    fn call<R: UnitRng>(self, rng: &mut R) -> Vec<u64> {
+        let mut done = 0;
+        let mut inside = 0;
+        self.counts
+            .iter()
+            .map(|&n| {
+                inside += count_inside(rng, n - done);
+                done = n;
+                inside
+            })
+            .collect()
+    }
+}
+
+impl PointFn<Vec<u64>> for CountAt<'_> {
+    fn call<P: PointSet>(self, points: &mut P) -> Vec<u64> {
+        let mut done = 0;
+        let mut inside = 0;
+        self.counts
+            .iter()
+            .map(|&n| {
+                inside += count_inside_points(points, n - done);
+                done = n;
+                inside
+            })
+            .collect()
+    }
+}
+
+/// Writes the traces as CSV with a header row, one row per source and count.
+pub fn write_csv<W: Write>(traces: &[Trace], mut out: W) -> io::Result<()> {
+    writeln!(out, "source,n,inside,estimate,abs_error,std_error")?;
+    for t in traces {
+        for p in &t.points {
+            writeln!(
+                out,
+                "{},{},{},{},{},{}",
+                t.source, p.n, p.inside, p.estimate, p.abs_error, p.std_error
+            )?;
+        }
+    }
+    Ok(())
+}
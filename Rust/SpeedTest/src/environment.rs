@@ -0,0 +1,328 @@
+//! The state of the system while benchmarks run, and knobs to quieten it.
+//!
+//! Frequency scaling, load from background services such as `rkipc`, heat
+//! and migrations between cores all show up as noise in the timings. The
+//! runner records what it can see here with every result and warns when the
+//! conditions look poor.
+
+use std::fs;
+use std::io;
+use std::path::Path;
+
+use serde::{Deserialize, Serialize};
+
+use crate::host::read_trimmed;
+
+/// Services known to load the LuckFox CPU in the background.
+pub const NOISY_SERVICES: &[&str] = &["rkipc"];
+
+/// Temperature at which thermal throttling becomes a concern.
+const HOT_CELSIUS: f64 = 70.0;
+
+/// cpufreq state of one CPU. Fields are `None` when the kernel does not
+/// expose them.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct CpuFreq {
+    pub cpu: usize,
+    pub governor: Option<String>,
+    pub cur_khz: Option<u64>,
+    pub min_khz: Option<u64>,
+    pub max_khz: Option<u64>,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ThermalZone {
+    pub zone: String,
+    pub temp_celsius: f64,
+}
+
+/// A snapshot of the conditions a benchmark ran under.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct Environment {
+    pub cpufreq: Vec<CpuFreq>,
+    /// 1, 5 and 15 minute load averages.
+    pub load_avg: Option<[f64; 3]>,
+    pub thermal: Vec<ThermalZone>,
+    /// CPUs this process may run on.
+    pub affinity: Vec<usize>,
+    /// The process's nice value.
+    pub nice: Option<i32>,
+    /// Entries of [`NOISY_SERVICES`] that are running.
+    pub noisy_services: Vec<String>,
+}
+
+impl Environment {
+    pub fn capture() -> Environment {
+        Environment {
+            cpufreq: cpufreq(),
+            load_avg: load_avg(),
+            thermal: thermal(),
+            affinity: sys::affinity().unwrap_or_default(),
+            nice: sys::nice().ok(),
+            noisy_services: running_services(NOISY_SERVICES),
+        }
+    }
+
+    /// cpufreq state of the CPUs this process may run on.
+    pub fn active_cpufreq(&self) -> impl Iterator<Item = &CpuFreq> {
+        self.cpufreq
+            .iter()
+            .filter(|f| self.affinity.is_empty() || self.affinity.contains(&f.cpu))
+    }
+
+    pub fn max_temp_celsius(&self) -> Option<f64> {
+        self.thermal
+            .iter()
+            .map(|t| t.temp_celsius)
+            .max_by(f64::total_cmp)
+    }
+
+    /// Reasons to distrust timings taken under these conditions.
+    pub fn noise_warnings(&self) -> Vec<String> {
+        let mut warnings = Vec::new();
+        for f in self.active_cpufreq() {
+            if let Some(g) = f.governor.as_deref().filter(|&g| g != "performance") {
+                warnings.push(format!(
+                    "cpu{} uses the `{}` governor, so its frequency can change mid-run",
+                    f.cpu, g
+                ));
+            }
+            if let (Some(cur), Some(max)) = (f.cur_khz, f.max_khz) {
+                if cur < max {
+                    warnings.push(format!(
+                        "cpu{} runs at {} MHz, below its maximum of {} MHz",
+                        f.cpu,
+                        cur / 1000,
+                        max / 1000
+                    ));
+                }
+            }
+        }
+        if let Some([load1, _, _]) = self.load_avg {
+            let cpus = self.affinity.len().max(1) as f64;
+            if load1 > 0.5 * cpus {
+                warnings.push(format!(
+                    "1-minute load average is {:.2} on {} CPU(s)",
+                    load1, cpus
+                ));
+            }
+        }
+        if let Some(t) = self.max_temp_celsius().filter(|&t| t >= HOT_CELSIUS) {
+            warnings.push(format!("a thermal zone is at {:.1} C and may throttle", t));
+        }
+        for s in &self.noisy_services {
+            warnings.push(format!("background service `{}` is running", s));
+        }
+        warnings
+    }
+
+    /// One line for the table output.
+    pub fn summary(&self) -> String {
+        let mut parts = Vec::new();
+        if let Some(f) = self.active_cpufreq().next() {
+            if let Some(g) = &f.governor {
+                parts.push(format!("governor {}", g));
+            }
+            if let Some(cur) = f.cur_khz {
+                parts.push(format!("{} MHz", cur / 1000));
+            }
+        }
+        if let Some([l1, l5, l15]) = self.load_avg {
+            parts.push(format!("load {:.2} {:.2} {:.2}", l1, l5, l15));
+        }
+        if let Some(t) = self.max_temp_celsius() {
+            parts.push(format!("max temp {:.1} C", t));
+        }
+        if !self.affinity.is_empty() {
+            parts.push(format!("cpus {}", cpu_list(&self.affinity)));
+        }
+        if let Some(n) = self.nice {
+            parts.push(format!("nice {}", n));
+        }
+        parts.join(", ")
+    }
+}
+
+/// Formats a CPU set compactly, e.g. `0-3,6`.
+pub fn cpu_list(cpus: &[usize]) -> String {
+    let mut out: Vec<String> = Vec::new();
+    let mut i = 0;
+    while i < cpus.len() {
+        let mut j = i;
+        while j + 1 < cpus.len() && cpus[j + 1] == cpus[j] + 1 {
+            j += 1;
+        }
+        out.push(if i == j {
+            cpus[i].to_string()
+        } else {
+            format!("{}-{}", cpus[i], cpus[j])
+        });
+        i = j + 1;
+    }
+    out.join(",")
+}
+
+/// Restricts this process to one CPU so the scheduler cannot migrate it.
+pub fn pin_to_cpu(cpu: usize) -> io::Result<()> {
+    sys::pin_to_cpu(cpu)
+}
+
+/// Sets this process's nice value; negative values need root or
+/// `CAP_SYS_NICE`.
+pub fn set_nice(nice: i32) -> io::Result<()> {
+    sys::set_nice(nice)
+}
+
+fn cpufreq() -> Vec<CpuFreq> {
+    let Ok(entries) = fs::read_dir("/sys/devices/system/cpu") else {
+        return Vec::new();
+    };
+    let mut cpus: Vec<CpuFreq> = entries
+        .filter_map(|e| {
+            let name = e.ok()?.file_name().into_string().ok()?;
+            let cpu: usize = name.strip_prefix("cpu")?.parse().ok()?;
+            let dir = format!("/sys/devices/system/cpu/{}/cpufreq", name);
+            if !Path::new(&dir).exists() {
+                return None;
+            }
+            let num = |file: &str| read_trimmed(&format!("{}/{}", dir, file))?.parse().ok();
+            Some(CpuFreq {
+                cpu,
+                governor: read_trimmed(&format!("{}/scaling_governor", dir)),
+                cur_khz: num("scaling_cur_freq"),
+                min_khz: num("scaling_min_freq"),
+                max_khz: num("scaling_max_freq"),
+            })
+        })
+        .collect();
+    cpus.sort_by_key(|f| f.cpu);
+    cpus
+}
+
+fn load_avg() -> Option<[f64; 3]> {
+    let s = read_trimmed("/proc/loadavg")?;
+    let mut it = s.split_whitespace().map(|v| v.parse::<f64>().ok());
+    Some([it.next()??, it.next()??, it.next()??])
+}
+
+/// Thermal zone temperatures; the kernel reports millidegrees.
+fn thermal() -> Vec<ThermalZone> {
+    let Ok(entries) = fs::read_dir("/sys/class/thermal") else {
+        return Vec::new();
+    };
+    let mut zones: Vec<ThermalZone> = entries
+        .filter_map(|e| {
+            let path = e.ok()?.path();
+            let name = path.file_name()?.to_str()?;
+            if !name.starts_with("thermal_zone") {
+                return None;
+            }
+            let dir = path.to_str()?;
+            let millideg: f64 = read_trimmed(&format!("{}/temp", dir))?.parse().ok()?;
+            Some(ThermalZone {
+                zone: read_trimmed(&format!("{}/type", dir)).unwrap_or_else(|| name.to_string()),
+                temp_celsius: millideg / 1000.0,
+            })
+        })
+        .collect();
+    zones.sort_by(|a, b| a.zone.cmp(&b.zone));
+    zones
+}
+
+/// Which of `names` appear as a process command name in `/proc`.
+fn running_services(names: &[&str]) -> Vec<String> {
+    let Ok(entries) = fs::read_dir("/proc") else {
+        return Vec::new();
+    };
+    let mut found: Vec<String> = entries
+        .filter_map(|e| {
+            let path = e.ok()?.path();
+            let comm = read_trimmed(path.join("comm").to_str()?)?;
+            names.contains(&comm.as_str()).then_some(comm)
+        })
+        .collect();
+    found.sort();
+    found.dedup();
+    found
+}
+
+#[cfg(target_os = "linux")]
+mod sys {
+    use std::io;
+
+    pub fn affinity() -> io::Result<Vec<usize>> {
+        // SAFETY: an all-zero cpu_set_t is a valid empty set, and
+        // sched_getaffinity writes at most size_of::<cpu_set_t>() bytes.
+        unsafe {
+            let mut set: libc::cpu_set_t = std::mem::zeroed();
+            if libc::sched_getaffinity(0, std::mem::size_of::<libc::cpu_set_t>(), &mut set) != 0 {
+                return Err(io::Error::last_os_error());
+            }
+            Ok((0..libc::CPU_SETSIZE as usize)
+                .filter(|&cpu| libc::CPU_ISSET(cpu, &set))
+                .collect())
+        }
+    }
+
+    pub fn pin_to_cpu(cpu: usize) -> io::Result<()> {
+        if cpu >= libc::CPU_SETSIZE as usize {
+            return Err(io::Error::from(io::ErrorKind::InvalidInput));
+        }
+        // SAFETY: as above; `cpu` is within the set's bounds.
+        unsafe {
+            let mut set: libc::cpu_set_t = std::mem::zeroed();
+            libc::CPU_SET(cpu, &mut set);
+            if libc::sched_setaffinity(0, std::mem::size_of::<libc::cpu_set_t>(), &set) != 0 {
+                return Err(io::Error::last_os_error());
+            }
+        }
+        Ok(())
+    }
+
+    pub fn nice() -> io::Result<i32> {
+        // getpriority can legitimately return -1, so errno must be cleared
+        // and checked.
+        // SAFETY: plain libc calls on this process.
+        unsafe {
+            *libc::__errno_location() = 0;
+            let n = libc::getpriority(libc::PRIO_PROCESS, 0);
+            if n == -1 && *libc::__errno_location() != 0 {
+                return Err(io::Error::last_os_error());
+            }
+            Ok(n)
+        }
+    }
+
+    pub fn set_nice(nice: i32) -> io::Result<()> {
+        // SAFETY: plain libc call on this process.
+        if unsafe { libc::setpriority(libc::PRIO_PROCESS, 0, nice) } != 0 {
+            return Err(io::Error::last_os_error());
+        }
+        Ok(())
+    }
+}
+
+#[cfg(not(target_os = "linux"))]
+mod sys {
+    use std::io;
+
+    fn unsupported<T>() -> io::Result<T> {
+        Err(io::Error::from(io::ErrorKind::Unsupported))
+    }
+
+    pub fn affinity() -> io::Result<Vec<usize>> {
+        unsupported()
+    }
+
+    pub fn pin_to_cpu(_cpu: usize) -> io::Result<()> {
+        unsupported()
+    }
+
+    pub fn nice() -> io::Result<i32> {
+        unsupported()
+    }
+
+    pub fn set_nice(_nice: i32) -> io::Result<()> {
+        unsupported()
+    }
+}
@@ -0,0 +1,75 @@
+//! The kernels of the C programs, compiled by `build.rs` into a static
+//! library and linked in, so they can be timed next to the Rust kernels in
+//! the same process.
+
+use std::os::raw::{c_longlong, c_uint};
+use std::sync::Mutex;
+
+extern "C" {
+    #[link_name = "loop_sum"]
+    fn c_loop_sum_raw(outer: c_longlong, inner: c_longlong, modulus: c_longlong) -> c_longlong;
+    #[link_name = "quad_res"]
+    pub(crate) fn c_quad_res_raw(n: c_longlong, m: c_longlong) -> c_longlong;
+    #[link_name = "count_inside_circle"]
+    fn c_count_inside_circle_raw(iterations: c_longlong) -> c_longlong;
+    fn srand(seed: c_uint);
+}
+
+/// Largest modulus for which `i * i` in the C `quad_res` cannot overflow, a
+/// signed overflow being undefined behaviour in C.
+pub const C_QUAD_RES_MAX_M: i64 = 3_037_000_499;
+
+/// `rand()` keeps global state, so seeding and drawing must not interleave
+/// between threads.
+static C_RAND: Mutex<()> = Mutex::new(());
+
+/// The loop from `loop_test.c`.
+///
+/// # Panics
+///
+/// If `sum + i + j` could overflow, or `modulus` is not positive.
+pub fn c_loop_sum(outer: i64, inner: i64, modulus: i64) -> i64 {
+    assert!(modulus > 0, "modulus {} is not positive", modulus);
+    assert!(
+        modulus
+            .checked_add(outer.max(0))
+            .and_then(|s| s.checked_add(inner.max(0)))
+            .is_some(),
+        "the C loop overflows for outer = {}, inner = {}, modulus = {}",
+        outer,
+        inner,
+        modulus
+    );
+    // SAFETY: the loop only does arithmetic, and the checks above rule out
+    // overflow and division by zero.
+    unsafe { c_loop_sum_raw(outer, inner, modulus) }
+}
+
+/// `quad_res` from `function_call.c`.
+///
+/// # Panics
+///
+/// If `m` is above [`C_QUAD_RES_MAX_M`].
+pub fn c_quad_res(n: i64, m: i64) -> i64 {
+    assert!(
+        m <= C_QUAD_RES_MAX_M,
+        "the C quad_res overflows for m = {}",
+        m
+    );
+    // SAFETY: `quad_res` only does arithmetic on its arguments, and `m` is
+    // small enough that none of it overflows.
+    unsafe { c_quad_res_raw(n, m) }
+}
+
+/// The circle count from `monte_carlo_pi.c`, after `srand(seed)`. With glibc
+/// this draws the same points as [`crate::rng::GlibcRand::new`]`(seed)`.
+pub fn c_count_inside_circle(seed: u32, iterations: u64) -> u64 {
+    let iterations = c_longlong::try_from(iterations).expect("iterations fit in a long long");
+    let _guard = C_RAND.lock().unwrap_or_else(|e| e.into_inner());
+    // SAFETY: the lock keeps other threads off `rand()`'s state, and the
+    // kernel only reads `rand()` and counts.
+    unsafe {
+        srand(seed);
+        c_count_inside_circle_raw(iterations) as u64
+    }
+}
@@ -0,0 +1,69 @@
+//! Detecting kernels the compiler has constant-folded away.
+//!
+//! A kernel that really runs takes time roughly proportional to its work. If
+//! scaling the input up leaves the time nearly flat, the work was done at
+//! compile time (or skipped) and the timing is meaningless.
+
+use crate::{bench, Benchmark, RunConfig};
+
+/// Work multipliers the check runs each benchmark at.
+pub const FACTORS: [u64; 3] = [1, 2, 4];
+
+/// A kernel is flagged when its time grows by less than this fraction of the
+/// growth in work.
+const MIN_GROWTH: f64 = 0.5;
+
+/// Timing of one benchmark across scaled input sizes.
+#[derive(Debug, Clone)]
+pub struct FoldCheck {
+    pub benchmark: &'static str,
+    /// `(factor, work, median ns)` for each entry of [`FACTORS`].
+    pub points: Vec<(u64, f64, f64)>,
+    /// Work at the largest factor over work at the smallest.
+    pub work_ratio: f64,
+    /// Median time at the largest factor over median time at the smallest.
+    pub time_ratio: f64,
+}
+
+/// What a [`FoldCheck`] concluded.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Outcome {
+    /// The time grew with the work.
+    NotFolded,
+    /// The time stayed nearly flat while the work grew.
+    LooksFolded,
+    /// The work did not grow, e.g. because it is zero at this size, so the
+    /// timings say nothing either way.
+    Inconclusive,
+}
+
+impl FoldCheck {
+    pub fn outcome(&self) -> Outcome {
+        // A NaN ratio, from zero work, is inconclusive too.
+        if self.work_ratio.is_nan() || self.work_ratio <= 1.0 {
+            Outcome::Inconclusive
+        } else if self.time_ratio < MIN_GROWTH * self.work_ratio {
+            Outcome::LooksFolded
+        } else {
+            Outcome::NotFolded
+        }
+    }
+}
+
+pub fn check_not_folded(bench: &dyn Benchmark, config: &RunConfig) -> FoldCheck {
+    let points: Vec<(u64, f64, f64)> = FACTORS
+        .iter()
+        .map(|&factor| {
+            let scaled = bench.scaled(factor);
+            let m = bench::measure(scaled.as_ref(), config);
+            (factor, scaled.work(), m.summary.median)
+        })
+        .collect();
+    let (first, last) = (points[0], points[points.len() - 1]);
+    FoldCheck {
+        benchmark: bench.name(),
+        work_ratio: last.1 / first.1,
+        time_ratio: last.2 / first.2,
+        points,
+    }
+}
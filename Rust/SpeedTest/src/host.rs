@@ -0,0 +1,57 @@
+//! Facts about the machine a run happened on.
+
+use std::fs;
+
+use serde::{Deserialize, Serialize};
+
+/// Identifies the board and toolchain behind a set of results.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct HostInfo {
+    pub hostname: String,
+    pub cpu_model: String,
+    pub kernel: String,
+    pub rustc: String,
+}
+
+impl HostInfo {
+    pub fn detect() -> HostInfo {
+        HostInfo {
+            hostname: read_trimmed("/proc/sys/kernel/hostname")
+                .or_else(|| read_trimmed("/etc/hostname"))
+                .unwrap_or_else(unknown),
+            cpu_model: cpu_model().unwrap_or_else(unknown),
+            kernel: read_trimmed("/proc/sys/kernel/osrelease").unwrap_or_else(unknown),
+            rustc: env!("SPEEDTEST_RUSTC_VERSION").to_string(),
+        }
+    }
+}
+
+fn unknown() -> String {
+    "unknown".to_string()
+}
+
+pub(crate) fn read_trimmed(path: &str) -> Option<String> {
+    let s = fs::read_to_string(path).ok()?;
+    let s = s.trim();
+    if s.is_empty() {
+        None
+    } else {
+        Some(s.to_string())
+    }
+}
+
+/// Reads the CPU model from `/proc/cpuinfo`. x86 kernels report `model name`;
+/// ARM kernels such as the LuckFox's use `Hardware` or `Processor` instead.
+fn cpu_model() -> Option<String> {
+    let cpuinfo = fs::read_to_string("/proc/cpuinfo").ok()?;
+    for key in ["model name", "Hardware", "Processor", "cpu model"] {
+        let found = cpuinfo.lines().find_map(|line| {
+            let (k, v) = line.split_once(':')?;
+            (k.trim() == key && !v.trim().is_empty()).then(|| v.trim().to_string())
+        });
+        if found.is_some() {
+            return found;
+        }
+    }
+    None
+}
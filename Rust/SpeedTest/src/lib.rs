@@ -0,0 +1,32 @@
+//! Speed tests for the LuckFox boards.
+//!
+//! Every test lives in [`benches`] as a type implementing [`Benchmark`] and is
+//! registered in [`benches::registry`], so the `speedtest` binary can list,
+//! select and run them. The matching C and Python programs sit next to this
+//! crate in `Rust/SpeedTest`.
+
+pub mod baseline;
+pub mod bench;
+pub mod benches;
+pub mod compare;
+pub mod convergence;
+pub mod environment;
+pub mod ffi;
+pub mod folding;
+pub mod host;
+pub mod memory;
+pub mod perf;
+#[cfg(feature = "python")]
+pub mod python;
+pub mod qmc;
+pub mod report;
+pub mod residue;
+pub mod rng;
+pub mod scaling;
+pub mod simd;
+pub mod stats;
+pub mod sweep;
+
+pub use bench::{
+    Benchmark, Expected, Measurement, ParamError, RunConfig, Sample, Uncertainty, Value,
+};
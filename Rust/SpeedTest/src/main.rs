@@ -0,0 +1,1136 @@
+use std::fs::File;
+use std::io::{self, BufWriter, Write};
+use std::ops::RangeInclusive;
+use std::path::{Path, PathBuf};
+use std::process::ExitCode;
+
+use clap::{Parser, Subcommand};
+
+use speedtest::baseline::{self, Verdict};
+use speedtest::bench;
+use speedtest::benches;
+use speedtest::compare::{self, CompareConfig, Lang, Row};
+use speedtest::convergence::{self, Source};
+use speedtest::environment::{self, Environment};
+use speedtest::folding::{self, Outcome};
+use speedtest::host::HostInfo;
+use speedtest::memory::CountingAlloc;
+use speedtest::qmc::{self, Qmc};
+use speedtest::report::{Format, Record, Reporter};
+use speedtest::residue;
+use speedtest::rng::Backend;
+use speedtest::scaling::{self, ScalingPoint};
+use speedtest::stats::format_ns;
+use speedtest::sweep::{self, SweepConfig};
+use speedtest::{Benchmark, ParamError, RunConfig};
+
+// Counts heap allocations for `run --memory`.
+#[global_allocator]
+static ALLOC: CountingAlloc = CountingAlloc;
+
+/// Runs the LuckFox Rust speed tests.
+#[derive(Parser)]
+#[command(name = "speedtest", version, about)]
+struct Cli {
+    #[command(subcommand)]
+    command: Command,
+}
+
+#[derive(Subcommand)]
+enum Command {
+    /// List the registered benchmarks.
+    List,
+    /// Run the named benchmarks, or all of them when none are given.
+    Run {
+        /// Benchmark names or aliases, e.g. `loop monte_carlo_pi`.
+        names: Vec<String>,
+        /// Untimed runs before measuring.
+        #[arg(long, default_value_t = 1)]
+        warmup: usize,
+        /// Timed runs per benchmark.
+        #[arg(long, short = 'n', default_value_t = 10, value_parser = clap::value_parser!(u64).range(1..))]
+        runs: u64,
+        /// Output format.
+        #[arg(long, value_enum, default_value_t = Format::Table)]
+        format: Format,
+        /// Pin the process to this CPU before running.
+        #[arg(long, value_name = "CPU")]
+        pin_cpu: Option<usize>,
+        /// Set the process's nice value before running; negative values
+        /// raise priority and need root.
+        #[arg(long, allow_hyphen_values = true)]
+        nice: Option<i32>,
+        /// Collect hardware performance counters (cycles, instructions,
+        /// branch and cache misses, task clock) with perf_event_open.
+        #[arg(long)]
+        counters: bool,
+        /// Report peak RSS, page faults and heap allocations per run, and
+        /// flag tests that allocate inside their hot loop.
+        #[arg(long)]
+        memory: bool,
+        /// Set a parameter as `[benchmark.]key=value`, e.g. `m=8000` or
+        /// `loop.outer=2000`. Without a benchmark prefix it applies to every
+        /// selected test that has the parameter, and tests that cannot take
+        /// the value, e.g. a composite `m` for `quad_res_euler`, are skipped.
+        #[arg(
+            long = "param",
+            short = 'p',
+            env = "SPEEDTEST_PARAMS",
+            value_delimiter = ','
+        )]
+        params: Vec<String>,
+        /// Seed every test that takes one, making the run reproducible.
+        /// Without it each test picks a random seed and records it. A seed
+        /// set with `-p seed=...` or `-p <test>.seed=...` takes precedence.
+        #[arg(long)]
+        seed: Option<u64>,
+        /// Time each test at 1x, 2x and 4x its work and fail if the time does
+        /// not grow with it, which means the kernel was constant-folded.
+        #[arg(long)]
+        verify_not_folded: bool,
+        /// Time each test over a geometric series of sizes ending at its
+        /// configured size and fit how the time grows.
+        #[arg(long, conflicts_with = "verify_not_folded")]
+        sweep: bool,
+        /// Number of sizes in a sweep.
+        #[arg(long, default_value_t = 6, value_parser = clap::value_parser!(u64).range(2..))]
+        sweep_steps: u64,
+        /// Ratio between consecutive sweep sizes.
+        #[arg(long, default_value_t = 2.0)]
+        sweep_ratio: f64,
+        /// Save the results as a named baseline for `speedtest diff`.
+        #[arg(long, value_name = "NAME", value_parser = baseline::parse_name)]
+        save_baseline: Option<String>,
+        /// Directory baselines are stored in.
+        #[arg(long, env = "SPEEDTEST_BASELINE_DIR", default_value = baseline::DEFAULT_DIR)]
+        baseline_dir: PathBuf,
+    },
+    /// Re-run the benchmarks in a saved baseline and flag significant changes.
+    Diff {
+        /// Name of the saved baseline.
+        #[arg(value_parser = baseline::parse_name)]
+        baseline: String,
+        /// Only diff these benchmarks.
+        names: Vec<String>,
+        /// Compare against another saved baseline instead of running now.
+        #[arg(long, value_name = "NAME", value_parser = baseline::parse_name)]
+        against: Option<String>,
+        /// Smallest change of the median, in percent, that counts.
+        #[arg(long, default_value_t = 5.0)]
+        threshold: f64,
+        /// Significance level for the Mann-Whitney U test.
+        #[arg(long, default_value_t = 0.05)]
+        alpha: f64,
+        /// Untimed runs before measuring.
+        #[arg(long, default_value_t = 1)]
+        warmup: usize,
+        /// Timed runs per benchmark.
+        #[arg(long, short = 'n', default_value_t = 10, value_parser = clap::value_parser!(u64).range(1..))]
+        runs: u64,
+        /// Directory baselines are stored in.
+        #[arg(long, env = "SPEEDTEST_BASELINE_DIR", default_value = baseline::DEFAULT_DIR)]
+        baseline_dir: PathBuf,
+        /// Pin the process to this CPU before running.
+        #[arg(long, value_name = "CPU")]
+        pin_cpu: Option<usize>,
+        /// Set the process's nice value before running; negative values
+        /// raise priority and need root.
+        #[arg(long, allow_hyphen_values = true)]
+        nice: Option<i32>,
+    },
+    /// Measure strong and weak scaling of a multithreaded benchmark.
+    Scaling {
+        /// A benchmark with a `threads` parameter.
+        #[arg(default_value = "monte_carlo_pi_mt")]
+        name: String,
+        /// Largest thread count; defaults to the number of CPUs.
+        #[arg(long)]
+        max_threads: Option<u64>,
+        /// Untimed runs before measuring.
+        #[arg(long, default_value_t = 1)]
+        warmup: usize,
+        /// Timed runs per thread count.
+        #[arg(long, short = 'n', default_value_t = 5, value_parser = clap::value_parser!(u64).range(1..))]
+        runs: u64,
+        /// Set a parameter as `key=value`, e.g. `iterations=40000000`.
+        #[arg(long = "param", short = 'p', value_delimiter = ',')]
+        params: Vec<String>,
+    },
+    /// Trace how the Monte Carlo pi estimate converges with the sample count.
+    Converge {
+        /// Total samples; the trace ends here.
+        #[arg(long, default_value_t = 10_000_000, value_parser = clap::value_parser!(u64).range(1..))]
+        iterations: u64,
+        /// Generator, as for the `rng` parameter.
+        #[arg(long, default_value_t = Backend::ChaCha12)]
+        rng: Backend,
+        /// Seed; a random one is picked and printed when omitted.
+        #[arg(long)]
+        seed: Option<u64>,
+        /// Also trace Halton and Sobol points, plain and Owen scrambled, at
+        /// the same counts and show their errors side by side.
+        #[arg(long)]
+        compare: bool,
+        /// Halton bases for `--compare`.
+        #[arg(long, default_value = "2:3", value_parser = qmc::parse_bases)]
+        bases: [u32; 2],
+        /// Trace points per factor of ten samples.
+        #[arg(long, default_value_t = 4, value_parser = clap::value_parser!(u32).range(1..))]
+        per_decade: u32,
+        /// Also write the traces to this CSV file.
+        #[arg(long, value_name = "FILE")]
+        csv: Option<PathBuf>,
+    },
+    /// Check the fast quadratic-residue methods against the naive quad_res
+    /// for every modulus up to a limit.
+    CheckResidues {
+        /// Largest modulus to check; the naive test makes this O(m^3).
+        #[arg(long, default_value_t = 1000, value_parser = clap::value_parser!(u64).range(1..))]
+        up_to: u64,
+    },
+    /// Tabulate the quadratic residues mod each modulus, their square roots,
+    /// and the residue count against the closed-form formula.
+    Residues {
+        /// Moduli, or inclusive ranges such as `2-30`.
+        #[arg(required = true, value_parser = parse_moduli)]
+        moduli: Vec<RangeInclusive<u64>>,
+        /// Also list the square roots of every residue.
+        #[arg(long)]
+        roots: bool,
+        /// Only print the counts, which works for any modulus.
+        #[arg(long, conflicts_with = "roots")]
+        counts: bool,
+    },
+    /// Build and run the C, Python and Rust versions and compare them.
+    Compare {
+        /// Benchmark names or aliases; all tests with C/Python versions when empty.
+        names: Vec<String>,
+        /// C compiler.
+        #[arg(long, env = "CC", default_value = "cc")]
+        cc: String,
+        /// C compiler flags, whitespace separated.
+        #[arg(long, default_value = "-O2")]
+        cflags: String,
+        /// Python interpreter.
+        #[arg(long, default_value = "python3")]
+        python: String,
+        /// Runs per language; the median time is reported.
+        #[arg(long, short = 'n', default_value_t = 3, value_parser = clap::value_parser!(u64).range(1..))]
+        runs: u64,
+        /// Directory holding the `.c` and `.py` files.
+        #[arg(long, default_value_os_t = compare::default_source_dir())]
+        source_dir: PathBuf,
+        /// Where to put the compiled C programs.
+        #[arg(long, default_value_os_t = std::env::temp_dir().join("speedtest-compare"))]
+        build_dir: PathBuf,
+    },
+}
+
+fn main() -> ExitCode {
+    let cli = Cli::parse();
+    match cli.command {
+        Command::List => {
+            list();
+            ExitCode::SUCCESS
+        }
+        Command::Run {
+            names,
+            warmup,
+            runs,
+            format,
+            pin_cpu,
+            nice,
+            counters,
+            memory,
+            params,
+            seed,
+            verify_not_folded,
+            sweep,
+            sweep_steps,
+            sweep_ratio,
+            save_baseline,
+            baseline_dir,
+        } => {
+            if let Err(e) = quieten(pin_cpu, nice) {
+                eprintln!("error: {}", e);
+                return ExitCode::FAILURE;
+            }
+            let mut selected = match select(&names) {
+                Ok(selected) => selected,
+                Err(unknown) => {
+                    eprintln!(
+                        "error: unknown benchmark `{}` (see `speedtest list`)",
+                        unknown
+                    );
+                    return ExitCode::FAILURE;
+                }
+            };
+            if let Err(e) = apply_params(&mut selected, &params) {
+                eprintln!("error: {}", e);
+                return ExitCode::FAILURE;
+            }
+            if let Some(seed) = seed {
+                if let Err(e) = apply_seed(&mut selected, seed, &params) {
+                    eprintln!("error: {}", e);
+                    return ExitCode::FAILURE;
+                }
+            }
+            let config = RunConfig {
+                warmup,
+                runs: runs as usize,
+                counters,
+                memory,
+            };
+            if verify_not_folded {
+                return run_fold_check(&selected, &config);
+            }
+            if sweep {
+                if sweep_ratio <= 1.0 {
+                    eprintln!("error: --sweep-ratio must be greater than 1");
+                    return ExitCode::FAILURE;
+                }
+                let sweep_config = SweepConfig {
+                    steps: sweep_steps as usize,
+                    ratio: sweep_ratio,
+                };
+                return run_sweep(&selected, &config, &sweep_config);
+            }
+            let records = match run(&selected, &config, format) {
+                Ok(records) => records,
+                Err(e) => {
+                    eprintln!("error: writing results: {}", e);
+                    return ExitCode::FAILURE;
+                }
+            };
+            if records.iter().any(|r| !r.verified) {
+                return ExitCode::FAILURE;
+            }
+            if let Some(name) = save_baseline {
+                match baseline::save(&baseline_dir, &name, &records) {
+                    Ok(path) => eprintln!("Saved baseline `{}` to {}", name, path.display()),
+                    Err(e) => {
+                        eprintln!("error: saving baseline `{}`: {}", name, e);
+                        return ExitCode::FAILURE;
+                    }
+                }
+            }
+            ExitCode::SUCCESS
+        }
+        Command::Diff {
+            baseline,
+            names,
+            against,
+            threshold,
+            alpha,
+            warmup,
+            runs,
+            baseline_dir,
+            pin_cpu,
+            nice,
+        } => {
+            if let Err(e) = quieten(pin_cpu, nice) {
+                eprintln!("error: {}", e);
+                return ExitCode::FAILURE;
+            }
+            let config = RunConfig {
+                warmup,
+                runs: runs as usize,
+                counters: false,
+                memory: false,
+            };
+            run_diff(
+                &baseline_dir,
+                &baseline,
+                &names,
+                against.as_deref(),
+                &config,
+                alpha,
+                threshold,
+            )
+        }
+        Command::Scaling {
+            name,
+            max_threads,
+            warmup,
+            runs,
+            params,
+        } => {
+            let mut selected = match select(std::slice::from_ref(&name)) {
+                Ok(selected) => selected,
+                Err(unknown) => {
+                    eprintln!(
+                        "error: unknown benchmark `{}` (see `speedtest list`)",
+                        unknown
+                    );
+                    return ExitCode::FAILURE;
+                }
+            };
+            if let Err(e) = apply_params(&mut selected, &params) {
+                eprintln!("error: {}", e);
+                return ExitCode::FAILURE;
+            }
+            let max_threads = max_threads
+                .unwrap_or_else(|| {
+                    std::thread::available_parallelism().map_or(1, |n| n.get() as u64)
+                })
+                .max(1);
+            let config = RunConfig {
+                warmup,
+                runs: runs as usize,
+                counters: false,
+                memory: false,
+            };
+            run_scaling(selected[0].as_ref(), &config, max_threads)
+        }
+        Command::Converge {
+            iterations,
+            rng,
+            seed,
+            compare,
+            bases,
+            per_decade,
+            csv,
+        } => {
+            let mut sources = vec![Source::Random(rng)];
+            if compare {
+                for sequence in qmc::Sequence::ALL {
+                    for scramble in [false, true] {
+                        sources.push(Source::Qmc(Qmc {
+                            sequence,
+                            bases,
+                            scramble,
+                        }));
+                    }
+                }
+            }
+            run_converge(
+                &sources,
+                seed.unwrap_or_else(rand::random),
+                iterations,
+                per_decade,
+                csv.as_deref(),
+            )
+        }
+        Command::CheckResidues { up_to } => run_check_residues(up_to),
+        Command::Residues {
+            moduli,
+            roots,
+            counts,
+        } => {
+            let moduli: Vec<u64> = moduli.into_iter().flatten().collect();
+            run_residues(&moduli, roots, counts)
+        }
+        Command::Compare {
+            names,
+            cc,
+            cflags,
+            python,
+            runs,
+            source_dir,
+            build_dir,
+        } => {
+            let selected = match select(&names) {
+                Ok(selected) => selected,
+                Err(unknown) => {
+                    eprintln!(
+                        "error: unknown benchmark `{}` (see `speedtest list`)",
+                        unknown
+                    );
+                    return ExitCode::FAILURE;
+                }
+            };
+            let config = CompareConfig {
+                source_dir,
+                build_dir,
+                cc,
+                cflags: cflags.split_whitespace().map(String::from).collect(),
+                python,
+                runs: runs as usize,
+            };
+            run_compare(&selected, &config, !names.is_empty())
+        }
+    }
+}
+
+fn list() {
+    for b in benches::registry() {
+        println!("{:<16} {}", b.name(), b.description());
+        let params: Vec<String> = b
+            .params()
+            .iter()
+            .map(|(k, v)| format!("{}={}", k, v))
+            .collect();
+        if !params.is_empty() {
+            println!("{:<16} defaults: {}", "", params.join(" "));
+        }
+    }
+}
+
+/// Resolves the requested names, keeping the command-line order.
+fn select(names: &[String]) -> Result<Vec<Box<dyn Benchmark>>, String> {
+    if names.is_empty() {
+        return Ok(benches::registry());
+    }
+    names
+        .iter()
+        .map(|name| benches::find(name).ok_or_else(|| name.clone()))
+        .collect()
+}
+
+/// Applies `[benchmark.]key=value` settings to the selected tests.
+///
+/// A value a test does not support is an error when the test is named, and
+/// drops the test from the selection, with a warning, when the setting has no
+/// prefix.
+fn apply_params(selected: &mut Vec<Box<dyn Benchmark>>, params: &[String]) -> Result<(), String> {
+    let mut skipped = vec![false; selected.len()];
+    for param in params {
+        let (target, value) = param
+            .split_once('=')
+            .ok_or_else(|| format!("parameter `{}` is not of the form key=value", param))?;
+        let (bench_name, key) = match target.split_once('.') {
+            Some((b, k)) => (Some(b), k),
+            None => (None, target),
+        };
+        let mut applied = false;
+        for (b, skipped) in selected.iter_mut().zip(&mut skipped) {
+            if bench_name.is_some_and(|name| !b.matches(name)) {
+                continue;
+            }
+            match b.set_param(key, value) {
+                Ok(()) => applied = true,
+                Err(ParamError::Unknown(_)) if bench_name.is_none() => {}
+                Err(e @ ParamError::Unsupported { .. }) if bench_name.is_none() => {
+                    eprintln!("warning: skipping {}: {}", b.name(), e);
+                    applied = true;
+                    *skipped = true;
+                }
+                Err(e) => return Err(format!("{}: {}", b.name(), e)),
+            }
+        }
+        if !applied {
+            return Err(format!(
+                "no selected benchmark has a parameter `{}`",
+                target
+            ));
+        }
+    }
+    let mut skipped = skipped.into_iter();
+    selected.retain(|_| !skipped.next().unwrap_or(false));
+    if selected.is_empty() {
+        return Err("every selected benchmark was skipped".to_string());
+    }
+    Ok(())
+}
+
+/// Applies `--pin-cpu` and `--nice`, then warns about anything in the
+/// environment that is likely to make the timings noisy.
+fn quieten(pin_cpu: Option<usize>, nice: Option<i32>) -> Result<(), String> {
+    if let Some(cpu) = pin_cpu {
+        environment::pin_to_cpu(cpu).map_err(|e| format!("pinning to cpu{}: {}", cpu, e))?;
+    }
+    if let Some(n) = nice {
+        environment::set_nice(n).map_err(|e| format!("setting nice {}: {}", n, e))?;
+    }
+    for w in Environment::capture().noise_warnings() {
+        eprintln!("warning: {}", w);
+    }
+    Ok(())
+}
+
+/// Sets `seed` on every selected test that has a seed parameter, except
+/// those given a seed explicitly in `params`, which wins.
+fn apply_seed(
+    selected: &mut [Box<dyn Benchmark>],
+    seed: u64,
+    params: &[String],
+) -> Result<(), String> {
+    for b in selected.iter_mut() {
+        let explicit = params
+            .iter()
+            .filter_map(|p| p.split_once('=').map(|(target, _)| target))
+            .any(|target| match target.split_once('.') {
+                Some((name, key)) => key == "seed" && b.matches(name),
+                None => target == "seed",
+            });
+        if explicit {
+            continue;
+        }
+        match b.set_param("seed", &seed.to_string()) {
+            // Tests without a seed are deterministic already.
+            Ok(()) | Err(ParamError::Unknown(_)) => {}
+            Err(e) => return Err(format!("{}: {}", b.name(), e)),
+        }
+    }
+    Ok(())
+}
+
+/// Runs and reports the selected tests, returning their records.
+fn run(
+    selected: &[Box<dyn Benchmark>],
+    config: &RunConfig,
+    format: Format,
+) -> io::Result<Vec<Record>> {
+    let host = HostInfo::detect();
+    let mut records: Vec<Record> = Vec::with_capacity(selected.len());
+    let mut reporter = Reporter::new(format, io::stdout().lock());
+    let mut warned_counters = false;
+    for b in selected {
+        eprintln!(
+            "Starting {} test ({} warmup, {} timed runs)",
+            b.name(),
+            config.warmup,
+            config.runs
+        );
+        let env = Environment::capture();
+        let m = bench::measure(b.as_ref(), config);
+        if !m.missing_counters.is_empty() && !warned_counters {
+            eprintln!(
+                "warning: some performance counters are unavailable: {}",
+                m.missing_counters.join("; ")
+            );
+            warned_counters = true;
+        }
+        let mut record = Record::new(b.as_ref(), config, &m, &host, &env);
+        if let Some(reference) = records
+            .iter()
+            .find(|r| Some(&r.benchmark) == record.reference.as_ref())
+        {
+            record.compare_to_reference(reference);
+        }
+        reporter.write(&record)?;
+        if record
+            .memory
+            .and_then(|m| m.heap)
+            .is_some_and(|h| h.allocates_in_loop)
+        {
+            eprintln!("warning: {} allocates inside its hot loop", b.name());
+        }
+        if let Some(e) = &record.verify_error {
+            eprintln!("error: {} computed a wrong answer: {}", b.name(), e);
+        }
+        records.push(record);
+    }
+    Ok(records)
+}
+
+/// Diffs a saved baseline against a fresh run with the same parameters, or
+/// against another saved baseline. Fails on any regression.
+fn run_diff(
+    dir: &Path,
+    base_name: &str,
+    names: &[String],
+    against: Option<&str>,
+    config: &RunConfig,
+    alpha: f64,
+    threshold: f64,
+) -> ExitCode {
+    let load = |name: &str| {
+        baseline::load(dir, name)
+            .map_err(|e| eprintln!("error: loading baseline `{}`: {}", name, e))
+    };
+    let Ok(base) = load(base_name) else {
+        return ExitCode::FAILURE;
+    };
+    let base: Vec<Record> = base
+        .into_iter()
+        .filter(|r| {
+            names.is_empty()
+                || names
+                    .iter()
+                    .any(|n| benches::find(n).is_some_and(|b| b.name() == r.benchmark))
+        })
+        .collect();
+    let new = match against {
+        Some(name) => match load(name) {
+            Ok(records) => records,
+            Err(()) => return ExitCode::FAILURE,
+        },
+        None => match rerun(&base, config) {
+            Ok(records) => records,
+            Err(e) => {
+                eprintln!("error: {}", e);
+                return ExitCode::FAILURE;
+            }
+        },
+    };
+
+    let mut ok = true;
+    println!(
+        "{:<16} {:>14} {:>14} {:>9} {:>9}  verdict",
+        "benchmark", "baseline", "now", "change", "p"
+    );
+    for b in &base {
+        let Some(n) = new.iter().find(|n| n.benchmark == b.benchmark) else {
+            println!(
+                "{:<16} {:>14} {:>14}",
+                b.benchmark,
+                format_ns(b.timing.median),
+                "missing"
+            );
+            continue;
+        };
+        if n.params != b.params {
+            eprintln!(
+                "warning: {} parameters differ from the baseline",
+                b.benchmark
+            );
+        }
+        let c = baseline::diff(b, n, alpha, threshold);
+        let verdict = match c.verdict {
+            Verdict::Regressed => {
+                ok = false;
+                "REGRESSED"
+            }
+            Verdict::Improved => "improved",
+            Verdict::Unchanged => "unchanged",
+        };
+        println!(
+            "{:<16} {:>14} {:>14} {:>+8.1}% {:>9.4}  {}",
+            c.benchmark,
+            format_ns(c.base_median_ns),
+            format_ns(c.new_median_ns),
+            c.change_pct,
+            c.p_value,
+            verdict
+        );
+    }
+    if ok {
+        ExitCode::SUCCESS
+    } else {
+        ExitCode::FAILURE
+    }
+}
+
+/// Runs each benchmark in `base` again with the parameters it was saved with.
+fn rerun(base: &[Record], config: &RunConfig) -> Result<Vec<Record>, String> {
+    let host = HostInfo::detect();
+    let mut records = Vec::with_capacity(base.len());
+    for r in base {
+        let mut b = benches::find(&r.benchmark)
+            .ok_or_else(|| format!("baseline has unknown benchmark `{}`", r.benchmark))?;
+        for (k, v) in &r.params {
+            b.set_param(k, v)
+                .map_err(|e| format!("{}: {}", r.benchmark, e))?;
+        }
+        eprintln!("Running {} ({} timed runs)", b.name(), config.runs);
+        let env = Environment::capture();
+        let m = bench::measure(b.as_ref(), config);
+        let record = Record::new(b.as_ref(), config, &m, &host, &env);
+        if let Some(e) = &record.verify_error {
+            return Err(format!("{} computed a wrong answer: {}", r.benchmark, e));
+        }
+        records.push(record);
+    }
+    Ok(records)
+}
+
+/// Checks each selected test for constant folding. Fails if any kernel's time
+/// does not grow with its work.
+fn run_fold_check(selected: &[Box<dyn Benchmark>], config: &RunConfig) -> ExitCode {
+    let mut ok = true;
+    for b in selected {
+        eprintln!("Checking {} for constant folding", b.name());
+        let check = folding::check_not_folded(b.as_ref(), config);
+        println!("{}", check.benchmark);
+        for (factor, work, ns) in &check.points {
+            println!(
+                "  {}x work ({:.0} units)  median {}",
+                factor,
+                work,
+                format_ns(*ns)
+            );
+        }
+        match check.outcome() {
+            Outcome::Inconclusive => {
+                ok = false;
+                println!("  work did not grow with the size: inconclusive");
+            }
+            outcome => {
+                let verdict = if outcome == Outcome::LooksFolded {
+                    ok = false;
+                    "LOOKS FOLDED"
+                } else {
+                    "ok"
+                };
+                println!(
+                    "  {:.1}x work took {:.2}x time: {}",
+                    check.work_ratio, check.time_ratio, verdict
+                );
+            }
+        }
+    }
+    if ok {
+        ExitCode::SUCCESS
+    } else {
+        ExitCode::FAILURE
+    }
+}
+
+/// Sweeps each selected test over its sizes and prints the fitted complexity.
+fn run_sweep(
+    selected: &[Box<dyn Benchmark>],
+    config: &RunConfig,
+    sweep_config: &SweepConfig,
+) -> ExitCode {
+    let mut ok = true;
+    for b in selected {
+        eprintln!("Sweeping {} over {} sizes", b.name(), sweep_config.steps);
+        let s = match sweep::sweep(b.as_ref(), config, sweep_config) {
+            Ok(s) => s,
+            Err(e) => {
+                eprintln!("error: {}: {}", b.name(), e);
+                ok = false;
+                continue;
+            }
+        };
+        println!("{}", s.benchmark);
+        println!(
+            "  {:>12} {:>14} {:>14} {:>14}",
+            s.param, "work", "median", "ns/work"
+        );
+        for p in &s.points {
+            println!(
+                "  {:>12} {:>14.0} {:>14} {:>14.4}{}",
+                p.size,
+                p.work,
+                format_ns(p.summary.median),
+                p.ns_per_work(),
+                if p.verify_error.is_some() {
+                    "  WRONG"
+                } else {
+                    ""
+                }
+            );
+            if let Some(e) = &p.verify_error {
+                eprintln!("error: {} at {}={}: {}", s.benchmark, s.param, p.size, e);
+                ok = false;
+            }
+        }
+        println!(
+            "  time ~ {}^{:.2} (r^2 {:.3}); work model predicts {}^{:.2}",
+            s.param, s.exponent, s.r_squared, s.param, s.work_exponent
+        );
+    }
+    if ok {
+        ExitCode::SUCCESS
+    } else {
+        ExitCode::FAILURE
+    }
+}
+
+fn run_scaling(bench: &dyn Benchmark, config: &RunConfig, max_threads: u64) -> ExitCode {
+    eprintln!(
+        "Measuring {} scaling up to {} threads",
+        bench.name(),
+        max_threads
+    );
+    let s = match scaling::scaling(bench, config, max_threads) {
+        Ok(s) => s,
+        Err(e) => {
+            eprintln!(
+                "error: {}: {} (scaling needs `threads` and `iterations`)",
+                bench.name(),
+                e
+            );
+            return ExitCode::FAILURE;
+        }
+    };
+    println!("{}", s.benchmark);
+    for (title, points) in [
+        ("strong scaling (fixed total work)", &s.strong),
+        ("weak scaling (fixed work per thread)", &s.weak),
+    ] {
+        println!("  {}", title);
+        println!(
+            "  {:>8} {:>12} {:>14} {:>9} {:>11}",
+            "threads", "iterations", "median", "speedup", "efficiency"
+        );
+        for p in points.iter() {
+            let ScalingPoint {
+                threads,
+                iterations,
+                median_ns,
+                speedup,
+                efficiency,
+            } = *p;
+            println!(
+                "  {:>8} {:>12} {:>14} {:>8.2}x {:>10.1}%",
+                threads,
+                iterations,
+                format_ns(median_ns),
+                speedup,
+                efficiency * 100.0
+            );
+        }
+    }
+    ExitCode::SUCCESS
+}
+
+/// Prints convergence traces of the pi estimate, one source in detail or
+/// several side by side, and optionally saves them as CSV.
+fn run_converge(
+    sources: &[Source],
+    seed: u64,
+    iterations: u64,
+    per_decade: u32,
+    csv: Option<&Path>,
+) -> ExitCode {
+    let mut traces = Vec::with_capacity(sources.len());
+    for &source in sources {
+        if let Source::Qmc(q) = source {
+            if q.sequence == qmc::Sequence::Sobol && iterations > qmc::SOBOL_POINTS {
+                eprintln!("error: sobol has only {} points", qmc::SOBOL_POINTS);
+                return ExitCode::FAILURE;
+            }
+        }
+        eprintln!(
+            "Tracing {} to {} samples (seed={})",
+            source, iterations, seed
+        );
+        traces.push(convergence::trace(source, seed, iterations, per_decade));
+    }
+    if let [t] = traces.as_slice() {
+        println!("pi from {} (seed={})", t.source, t.seed);
+        println!(
+            "  {:>12} {:>10} {:>12} {:>12}",
+            "n", "estimate", "abs error", "std error"
+        );
+        for p in &t.points {
+            println!(
+                "  {:>12} {:>10.6} {:>12.3e} {:>12.3e}",
+                p.n, p.estimate, p.abs_error, p.std_error
+            );
+        }
+        println!("  error ~ n^{:.3} (Monte Carlo ideal n^-0.5)", t.exponent);
+    } else {
+        // Every trace has the same counts, so the rows line up.
+        let width = traces
+            .iter()
+            .map(|t| t.source.to_string().len())
+            .max()
+            .unwrap_or(0)
+            .max(10);
+        println!("absolute error of pi (seed={})", seed);
+        print!("  {:>12}", "n");
+        for t in &traces {
+            print!(" {:>width$}", t.source.to_string());
+        }
+        println!(" {:>10}", "MC stderr");
+        for (row, p) in traces[0].points.iter().enumerate() {
+            print!("  {:>12}", p.n);
+            for t in &traces {
+                print!(" {:>width$.3e}", t.points[row].abs_error);
+            }
+            println!(" {:>10.3e}", p.std_error);
+        }
+        print!("  {:>12}", "exponent");
+        for t in &traces {
+            print!(" {:>width$.3}", t.exponent);
+        }
+        println!();
+    }
+    if let Some(path) = csv {
+        let written = File::create(path).and_then(|f| {
+            let mut out = BufWriter::new(f);
+            convergence::write_csv(&traces, &mut out)?;
+            out.flush()
+        });
+        if let Err(e) = written {
+            eprintln!("error: writing {}: {}", path.display(), e);
+            return ExitCode::FAILURE;
+        }
+        eprintln!("Wrote {}", path.display());
+    }
+    ExitCode::SUCCESS
+}
+
+/// Cross-checks every residue method for each modulus in `1..=up_to`.
+fn run_check_residues(up_to: u64) -> ExitCode {
+    eprintln!("Checking moduli 1 to {} against quad_res", up_to);
+    let mut primes = 0;
+    for m in 1..=up_to {
+        if let Err(e) = residue::cross_check(m) {
+            eprintln!("error: {}", e);
+            return ExitCode::FAILURE;
+        }
+        if residue::is_prime(m) {
+            primes += 1;
+        }
+    }
+    println!(
+        "sieve, crt, square roots and the closed-form count agree with quad_res for m = 1..={}",
+        up_to
+    );
+    println!(
+        "euler and jacobi agree with quad_res for the {} primes among them",
+        primes
+    );
+    ExitCode::SUCCESS
+}
+
+/// Parses a modulus `m` or an inclusive range `low-high`.
+fn parse_moduli(s: &str) -> Result<RangeInclusive<u64>, String> {
+    let parse = |s: &str| match s.trim().parse::<u64>() {
+        Ok(0) => Err("moduli start at 1".to_string()),
+        Ok(m) => Ok(m),
+        Err(e) => Err(format!("`{}`: {}", s, e)),
+    };
+    let (low, high) = match s.split_once('-') {
+        Some((low, high)) => (parse(low)?, parse(high)?),
+        None => (parse(s)?, parse(s)?),
+    };
+    if low > high {
+        return Err(format!("empty range {}", s));
+    }
+    Ok(low..=high)
+}
+
+/// Largest modulus whose residues are listed; the list comes from a sieve
+/// with one byte per residue class.
+const LIST_LIMIT: u64 = 1 << 24;
+
+/// Prints the count table, then unless `counts_only` the residues of each
+/// modulus, one line per modulus or, with `roots`, one line per residue.
+fn run_residues(moduli: &[u64], roots: bool, counts_only: bool) -> ExitCode {
+    if !counts_only {
+        if let Some(m) = moduli.iter().find(|&&m| m > LIST_LIMIT) {
+            eprintln!(
+                "error: {} has too many residues to list (at most {}); pass --counts",
+                m, LIST_LIMIT
+            );
+            return ExitCode::FAILURE;
+        }
+    }
+    let mut ok = true;
+    println!(
+        "{:>20}  {:<24} {:>20} {:>20}",
+        "m", "factors", "residues", "closed form"
+    );
+    for &m in moduli {
+        let closed = residue::count_closed_form(m);
+        // Counting by enumeration checks the formula; past the sieve's
+        // limit only the formula is available.
+        let enumerated = (m <= LIST_LIMIT).then(|| residue::count_sieve(m));
+        println!(
+            "{:>20}  {:<24} {:>20} {:>20}{}",
+            m,
+            residue::format_factors(&residue::factorize(m)),
+            enumerated.map_or("-".to_string(), |n| n.to_string()),
+            closed,
+            if enumerated.is_some_and(|n| n != closed) {
+                "  DIFFER"
+            } else {
+                ""
+            }
+        );
+        ok &= enumerated.is_none_or(|n| n == closed);
+    }
+    if !counts_only {
+        for &m in moduli {
+            let residues = residue::residues(m);
+            if roots {
+                let factors = residue::factorize(m);
+                println!();
+                println!("mod {}", m);
+                println!("{:>12}  roots", "n");
+                for n in residues {
+                    let roots = match residue::sqrt_mod_crt(n, &factors) {
+                        Ok(roots) => roots,
+                        Err(e) => {
+                            eprintln!("error: roots of {} mod {}: {}", n, m, e);
+                            return ExitCode::FAILURE;
+                        }
+                    };
+                    let roots: Vec<String> = roots.iter().map(|r| r.to_string()).collect();
+                    println!("{:>12}  {}", n, roots.join(" "));
+                }
+            } else {
+                let residues: Vec<String> = residues.iter().map(|n| n.to_string()).collect();
+                println!();
+                println!("residues mod {}: {}", m, residues.join(" "));
+            }
+        }
+    }
+    if ok {
+        ExitCode::SUCCESS
+    } else {
+        ExitCode::FAILURE
+    }
+}
+
+/// Compares the selected tests across languages. Tests without C/Python
+/// versions are an error only when they were asked for by name.
+fn run_compare(
+    selected: &[Box<dyn Benchmark>],
+    config: &CompareConfig,
+    explicit: bool,
+) -> ExitCode {
+    let mut rows = Vec::new();
+    let mut ok = true;
+    for b in selected {
+        if compare::source_stem(b.name()).is_none() && !explicit {
+            continue;
+        }
+        eprintln!("Comparing {} ({} runs per language)", b.name(), config.runs);
+        match compare::compare(b.as_ref(), config) {
+            Ok(row) => rows.push(row),
+            Err(e) => {
+                eprintln!("error: {}", e);
+                ok = false;
+            }
+        }
+    }
+    print_matrix(&rows);
+    for row in &rows {
+        for (lang, r) in [(Lang::C, &row.c), (Lang::Python, &row.python)] {
+            if let Err(e) = r {
+                eprintln!("warning: {} {}: {}", row.benchmark, lang, e);
+            }
+        }
+        if let Some(m) = &row.mismatch {
+            eprintln!("error: {} answers disagree: {}", row.benchmark, m);
+            ok = false;
+        }
+    }
+    if ok {
+        ExitCode::SUCCESS
+    } else {
+        ExitCode::FAILURE
+    }
+}
+
+fn print_matrix(rows: &[Row]) {
+    const LANGS: [Lang; 3] = [Lang::C, Lang::Python, Lang::Rust];
+    print!("{:<16}", "test");
+    for lang in LANGS {
+        print!(" {:>12}", format!("{} ms", lang));
+    }
+    for lang in [Lang::C, Lang::Rust] {
+        print!(" {:>11}", format!("{} vs Py", lang));
+    }
+    println!("  answers");
+    for row in rows {
+        print!("{:<16}", row.benchmark);
+        for lang in LANGS {
+            match row.get(lang) {
+                Some(r) => print!(" {:>12.3}", r.ms),
+                None => print!(" {:>12}", "n/a"),
+            }
+        }
+        for lang in [Lang::C, Lang::Rust] {
+            match row.speedup(lang) {
+                Some(x) => print!(" {:>10.1}x", x),
+                None => print!(" {:>11}", "n/a"),
+            }
+        }
+        println!(
+            "  {}",
+            if row.mismatch.is_some() {
+                "DIFFER"
+            } else {
+                "agree"
+            }
+        );
+    }
+}
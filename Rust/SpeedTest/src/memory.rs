@@ -0,0 +1,272 @@
+//! Memory use of a benchmark: resident set size and page faults from
+//! `/proc/self`, and heap allocations through a counting global allocator.
+//!
+//! The allocator only sees allocations made through Rust's global allocator.
+//! The C kernels and the embedded CPython call `malloc` directly, so their
+//! heap shows up in the resident set size and page faults only.
+
+use std::alloc::{GlobalAlloc, Layout, System};
+use std::fs;
+use std::hint::black_box;
+use std::sync::atomic::{AtomicBool, AtomicI64, AtomicU64, Ordering};
+use std::sync::OnceLock;
+
+use serde::{Deserialize, Serialize};
+
+use crate::Benchmark;
+
+/// A global allocator that counts allocations on top of [`System`].
+///
+/// It counts only between [`HeapSnapshot::take`] and [`HeapSnapshot::since`],
+/// which [`crate::bench::measure`] calls only for `config.memory`; the rest
+/// of the time each allocation costs one relaxed load.
+///
+/// The `speedtest` binary installs it; a program that wants heap figures
+/// from [`crate::bench::measure`] has to do the same:
+///
+/// ```ignore
+/// #[global_allocator]
+/// static ALLOC: speedtest::memory::CountingAlloc = speedtest::memory::CountingAlloc;
+/// ```
+pub struct CountingAlloc;
+
+static COUNTING: AtomicBool = AtomicBool::new(false);
+static ALLOCATIONS: AtomicU64 = AtomicU64::new(0);
+static ALLOCATED_BYTES: AtomicU64 = AtomicU64::new(0);
+/// Bytes allocated minus bytes freed since counting started; negative when
+/// blocks from before are freed.
+static NET_BYTES: AtomicI64 = AtomicI64::new(0);
+static PEAK_NET_BYTES: AtomicI64 = AtomicI64::new(0);
+
+impl CountingAlloc {
+    fn record_alloc(size: usize) {
+        if !COUNTING.load(Ordering::Relaxed) {
+            return;
+        }
+        ALLOCATIONS.fetch_add(1, Ordering::Relaxed);
+        ALLOCATED_BYTES.fetch_add(size as u64, Ordering::Relaxed);
+        let net = NET_BYTES.fetch_add(size as i64, Ordering::Relaxed) + size as i64;
+        PEAK_NET_BYTES.fetch_max(net, Ordering::Relaxed);
+    }
+
+    fn record_dealloc(size: usize) {
+        if COUNTING.load(Ordering::Relaxed) {
+            NET_BYTES.fetch_sub(size as i64, Ordering::Relaxed);
+        }
+    }
+}
+
+// SAFETY: every call is forwarded unchanged to `System`; the counters are
+// plain atomics and never allocate.
+unsafe impl GlobalAlloc for CountingAlloc {
+    unsafe fn alloc(&self, layout: Layout) -> *mut u8 {
+        let ptr = System.alloc(layout);
+        if !ptr.is_null() {
+            Self::record_alloc(layout.size());
+        }
+        ptr
+    }
+
+    unsafe fn alloc_zeroed(&self, layout: Layout) -> *mut u8 {
+        let ptr = System.alloc_zeroed(layout);
+        if !ptr.is_null() {
+            Self::record_alloc(layout.size());
+        }
+        ptr
+    }
+
+    unsafe fn dealloc(&self, ptr: *mut u8, layout: Layout) {
+        System.dealloc(ptr, layout);
+        Self::record_dealloc(layout.size());
+    }
+
+    unsafe fn realloc(&self, ptr: *mut u8, layout: Layout, new_size: usize) -> *mut u8 {
+        let new = System.realloc(ptr, layout, new_size);
+        if !new.is_null() {
+            // A reallocation counts as freeing the old block and allocating
+            // the new one.
+            Self::record_dealloc(layout.size());
+            Self::record_alloc(new_size);
+        }
+        new
+    }
+}
+
+/// The allocation counters when counting started.
+#[derive(Debug, Clone, Copy)]
+pub struct HeapSnapshot {
+    allocations: u64,
+    bytes: u64,
+}
+
+impl HeapSnapshot {
+    /// Starts counting, with the live-heap high-water mark at zero.
+    pub fn take() -> HeapSnapshot {
+        NET_BYTES.store(0, Ordering::Relaxed);
+        PEAK_NET_BYTES.store(0, Ordering::Relaxed);
+        let snapshot = HeapSnapshot {
+            allocations: ALLOCATIONS.load(Ordering::Relaxed),
+            bytes: ALLOCATED_BYTES.load(Ordering::Relaxed),
+        };
+        COUNTING.store(true, Ordering::Relaxed);
+        snapshot
+    }
+
+    /// Stops counting and returns the allocations since `self`, divided by
+    /// `runs`.
+    pub fn since(&self, runs: usize) -> HeapUsage {
+        COUNTING.store(false, Ordering::Relaxed);
+        let runs = runs as f64;
+        HeapUsage {
+            allocations: (ALLOCATIONS.load(Ordering::Relaxed) - self.allocations) as f64 / runs,
+            bytes: (ALLOCATED_BYTES.load(Ordering::Relaxed) - self.bytes) as f64 / runs,
+            peak_bytes: PEAK_NET_BYTES.load(Ordering::Relaxed).max(0) as u64,
+            allocates_in_loop: false,
+        }
+    }
+}
+
+/// Whether [`CountingAlloc`] is the global allocator, found by making one
+/// allocation while counting and seeing whether it was counted.
+pub fn counting() -> bool {
+    static INSTALLED: OnceLock<bool> = OnceLock::new();
+    *INSTALLED.get_or_init(|| {
+        let before = HeapSnapshot::take();
+        drop(black_box(Box::new(0u64)));
+        before.since(1).allocations > 0.0
+    })
+}
+
+/// Heap allocations of one run of a kernel, averaged over the timed runs.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Serialize, Deserialize)]
+pub struct HeapUsage {
+    /// Allocations per run, reallocations included.
+    pub allocations: f64,
+    /// Bytes allocated per run.
+    pub bytes: f64,
+    /// Highest live heap over the timed runs, above what was live before.
+    pub peak_bytes: u64,
+    /// The number of allocations grows with the work, so the kernel
+    /// allocates inside its hot loop rather than once per run.
+    #[serde(default)]
+    pub allocates_in_loop: bool,
+}
+
+/// Memory figures for one benchmark. Every field is `None` where the
+/// platform could not provide it.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Serialize, Deserialize)]
+pub struct MemoryUsage {
+    /// Resident set size before the timed runs, in bytes.
+    pub rss_bytes: Option<u64>,
+    /// Peak resident set size, in bytes. It covers the timed runs when the
+    /// kernel let the peak be reset, and the whole process otherwise.
+    pub peak_rss_bytes: Option<u64>,
+    /// Whether `peak_rss_bytes` was reset before the timed runs.
+    #[serde(default)]
+    pub peak_rss_reset: bool,
+    /// Minor page faults per run.
+    pub minor_faults: Option<f64>,
+    /// Major page faults per run, which had to read from storage.
+    pub major_faults: Option<f64>,
+    /// Heap allocations, when [`CountingAlloc`] is installed.
+    pub heap: Option<HeapUsage>,
+}
+
+/// The `/proc/self` figures before the timed runs.
+pub struct ProcSnapshot {
+    rss_bytes: Option<u64>,
+    peak_rss_reset: bool,
+    faults: Option<(u64, u64)>,
+}
+
+impl ProcSnapshot {
+    /// Resets the peak resident set size where the kernel allows it, then
+    /// reads the resident set size and page fault counts.
+    pub fn take() -> ProcSnapshot {
+        // Writing 5 to clear_refs resets VmHWM (Linux 4.0 and later).
+        let peak_rss_reset = fs::write("/proc/self/clear_refs", "5").is_ok();
+        ProcSnapshot {
+            rss_bytes: status_kib("VmRSS").map(|k| k * 1024),
+            peak_rss_reset,
+            faults: page_faults(),
+        }
+    }
+
+    /// The figures since `self`, with page faults divided by `runs`.
+    pub fn since(&self, runs: usize) -> MemoryUsage {
+        let faults = page_faults()
+            .zip(self.faults)
+            .map(|((minor, major), (minor0, major0))| {
+                (
+                    (minor - minor0) as f64 / runs as f64,
+                    (major - major0) as f64 / runs as f64,
+                )
+            });
+        MemoryUsage {
+            rss_bytes: self.rss_bytes,
+            peak_rss_bytes: status_kib("VmHWM").map(|k| k * 1024),
+            peak_rss_reset: self.peak_rss_reset,
+            minor_faults: faults.map(|f| f.0),
+            major_faults: faults.map(|f| f.1),
+            heap: None,
+        }
+    }
+}
+
+/// Least growth in allocations, when the work doubles, that counts as
+/// allocating in the hot loop; allocations in the loop double with it.
+const LOOP_GROWTH: f64 = 1.5;
+
+/// Least number of extra allocations, when the work doubles, that counts as
+/// allocating in the hot loop. Kernels that split their work into chunks,
+/// such as `monte_carlo_pi_mt` spawning a thread per chunk, make a few more
+/// allocations at twice the work without allocating per iteration.
+const LOOP_MIN_EXTRA: f64 = 64.0;
+
+/// Runs a copy of `bench` with twice the work and reports whether its
+/// allocations grow roughly with the work, compared to the `heap` counted
+/// per run. Allocations made once per run stay the same.
+pub fn allocates_in_loop(bench: &dyn Benchmark, heap: &HeapUsage) -> bool {
+    let scaled = bench.scaled(2);
+    if scaled.work() <= bench.work() {
+        return false;
+    }
+    let before = HeapSnapshot::take();
+    black_box(scaled.run());
+    let allocations = before.since(1).allocations;
+    allocations >= LOOP_GROWTH * heap.allocations && allocations - heap.allocations > LOOP_MIN_EXTRA
+}
+
+/// A `kB` field of `/proc/self/status`, e.g. `VmHWM`.
+fn status_kib(key: &str) -> Option<u64> {
+    let status = fs::read_to_string("/proc/self/status").ok()?;
+    status.lines().find_map(|line| {
+        let (k, v) = line.split_once(':')?;
+        (k == key).then(|| v.trim().trim_end_matches("kB").trim().parse().ok())?
+    })
+}
+
+/// Minor and major page faults from `/proc/self/stat`.
+fn page_faults() -> Option<(u64, u64)> {
+    let stat = fs::read_to_string("/proc/self/stat").ok()?;
+    // The command name can contain spaces, so count fields from after it;
+    // minflt and majflt are fields 10 and 12 of the whole line.
+    let fields: Vec<&str> = stat.rsplit_once(')')?.1.split_whitespace().collect();
+    Some((fields.get(7)?.parse().ok()?, fields.get(9)?.parse().ok()?))
+}
+
+/// Bytes in binary units, e.g. `3.4 MiB`.
+pub fn format_bytes(bytes: f64) -> String {
+    const UNITS: [&str; 4] = ["B", "KiB", "MiB", "GiB"];
+    let mut v = bytes;
+    let mut unit = 0;
+    while v >= 1024.0 && unit < UNITS.len() - 1 {
+        v /= 1024.0;
+        unit += 1;
+    }
+    if unit == 0 {
+        format!("{:.0} B", v)
+    } else {
+        format!("{:.1} {}", v, UNITS[unit])
+    }
+}
@@ -0,0 +1,279 @@
+//! Hardware performance counters through Linux `perf_event_open`.
+//!
+//! Counters are opened one by one for the calling thread, user space only,
+//! so they work with `perf_event_paranoid` up to 2. They are inherited by
+//! the threads it spawns, so multithreaded kernels such as
+//! `monte_carlo_pi_mt` are counted in full once their workers have joined.
+//! Any counter the kernel or the CPU refuses (containers, locked-down
+//! kernels, missing PMU drivers) is simply left out of the results.
+
+use serde::{Deserialize, Serialize};
+
+/// The events the harness asks for, in order.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Event {
+    Cycles,
+    Instructions,
+    BranchMisses,
+    CacheMisses,
+    TaskClock,
+}
+
+impl Event {
+    pub const ALL: [Event; 5] = [
+        Event::Cycles,
+        Event::Instructions,
+        Event::BranchMisses,
+        Event::CacheMisses,
+        Event::TaskClock,
+    ];
+
+    pub fn name(self) -> &'static str {
+        match self {
+            Event::Cycles => "cycles",
+            Event::Instructions => "instructions",
+            Event::BranchMisses => "branch-misses",
+            Event::CacheMisses => "cache-misses",
+            Event::TaskClock => "task-clock",
+        }
+    }
+}
+
+/// Counter totals for one run of a kernel, averaged over the timed runs.
+/// A field is `None` when its counter could not be opened.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Serialize, Deserialize)]
+pub struct Counters {
+    pub cycles: Option<f64>,
+    pub instructions: Option<f64>,
+    pub branch_misses: Option<f64>,
+    pub cache_misses: Option<f64>,
+    pub task_clock_ns: Option<f64>,
+}
+
+impl Counters {
+    /// Instructions per cycle.
+    pub fn ipc(&self) -> Option<f64> {
+        Some(self.instructions? / self.cycles?)
+    }
+
+    pub fn get(&self, event: Event) -> Option<f64> {
+        match event {
+            Event::Cycles => self.cycles,
+            Event::Instructions => self.instructions,
+            Event::BranchMisses => self.branch_misses,
+            Event::CacheMisses => self.cache_misses,
+            Event::TaskClock => self.task_clock_ns,
+        }
+    }
+
+    fn set(&mut self, event: Event, value: f64) {
+        let slot = match event {
+            Event::Cycles => &mut self.cycles,
+            Event::Instructions => &mut self.instructions,
+            Event::BranchMisses => &mut self.branch_misses,
+            Event::CacheMisses => &mut self.cache_misses,
+            Event::TaskClock => &mut self.task_clock_ns,
+        };
+        *slot = Some(value);
+    }
+
+    /// Every available counter divided by `work`, e.g. cycles per iteration.
+    pub fn per_work(&self, work: f64) -> Counters {
+        let div = |v: Option<f64>| v.map(|v| v / work);
+        Counters {
+            cycles: div(self.cycles),
+            instructions: div(self.instructions),
+            branch_misses: div(self.branch_misses),
+            cache_misses: div(self.cache_misses),
+            task_clock_ns: div(self.task_clock_ns),
+        }
+    }
+}
+
+pub use sys::PerfCounters;
+
+#[cfg(target_os = "linux")]
+mod sys {
+    use std::io;
+    use std::os::fd::{AsRawFd, FromRawFd, OwnedFd};
+
+    use super::{Counters, Event};
+
+    const PERF_TYPE_HARDWARE: u32 = 0;
+    const PERF_TYPE_SOFTWARE: u32 = 1;
+    const PERF_COUNT_HW_CPU_CYCLES: u64 = 0;
+    const PERF_COUNT_HW_INSTRUCTIONS: u64 = 1;
+    const PERF_COUNT_HW_CACHE_MISSES: u64 = 3;
+    const PERF_COUNT_HW_BRANCH_MISSES: u64 = 5;
+    const PERF_COUNT_SW_TASK_CLOCK: u64 = 1;
+
+    const PERF_FORMAT_TOTAL_TIME_ENABLED: u64 = 1 << 0;
+    const PERF_FORMAT_TOTAL_TIME_RUNNING: u64 = 1 << 1;
+
+    const ATTR_DISABLED: u64 = 1 << 0;
+    const ATTR_INHERIT: u64 = 1 << 1;
+    const ATTR_EXCLUDE_KERNEL: u64 = 1 << 5;
+    const ATTR_EXCLUDE_HV: u64 = 1 << 6;
+
+    const PERF_FLAG_FD_CLOEXEC: libc::c_ulong = 1 << 3;
+
+    const PERF_EVENT_IOC_ENABLE: libc::c_ulong = 0x2400;
+    const PERF_EVENT_IOC_DISABLE: libc::c_ulong = 0x2401;
+    const PERF_EVENT_IOC_RESET: libc::c_ulong = 0x2403;
+
+    /// `struct perf_event_attr` up to `PERF_ATTR_SIZE_VER5`.
+    #[repr(C)]
+    #[derive(Default)]
+    struct PerfEventAttr {
+        type_: u32,
+        size: u32,
+        config: u64,
+        sample_period: u64,
+        sample_type: u64,
+        read_format: u64,
+        flags: u64,
+        wakeup_events: u32,
+        bp_type: u32,
+        config1: u64,
+        config2: u64,
+        branch_sample_type: u64,
+        sample_regs_user: u64,
+        sample_stack_user: u32,
+        clockid: i32,
+        sample_regs_intr: u64,
+        aux_watermark: u32,
+        sample_max_stack: u16,
+        reserved: u16,
+    }
+
+    /// The counters that could be opened for the current thread.
+    pub struct PerfCounters {
+        open: Vec<(Event, OwnedFd)>,
+        /// Counters that failed to open, with the reason.
+        pub unavailable: Vec<(Event, io::Error)>,
+    }
+
+    impl PerfCounters {
+        pub fn open() -> PerfCounters {
+            let mut open = Vec::new();
+            let mut unavailable = Vec::new();
+            for event in Event::ALL {
+                match open_event(event) {
+                    Ok(fd) => open.push((event, fd)),
+                    Err(e) => unavailable.push((event, e)),
+                }
+            }
+            PerfCounters { open, unavailable }
+        }
+
+        pub fn reset(&self) {
+            self.ioctl_all(PERF_EVENT_IOC_RESET);
+        }
+
+        pub fn enable(&self) {
+            self.ioctl_all(PERF_EVENT_IOC_ENABLE);
+        }
+
+        pub fn disable(&self) {
+            self.ioctl_all(PERF_EVENT_IOC_DISABLE);
+        }
+
+        /// Reads the totals since the last reset, scaled up if the kernel had
+        /// to multiplex the counters, and divides them by `runs`.
+        pub fn read(&self, runs: usize) -> Counters {
+            let mut counters = Counters::default();
+            for (event, fd) in &self.open {
+                let mut buf = [0u64; 3];
+                let want = std::mem::size_of_val(&buf);
+                // SAFETY: `buf` is valid for `want` bytes and `fd` is open.
+                let n = unsafe { libc::read(fd.as_raw_fd(), buf.as_mut_ptr().cast(), want) };
+                if n as usize != want {
+                    continue;
+                }
+                let [value, enabled, running] = buf;
+                if running == 0 {
+                    continue;
+                }
+                let scaled = value as f64 * enabled as f64 / running as f64;
+                counters.set(*event, scaled / runs as f64);
+            }
+            counters
+        }
+
+        fn ioctl_all(&self, request: libc::c_ulong) {
+            for (_, fd) in &self.open {
+                // SAFETY: the perf ioctls take no argument and `fd` is open.
+                unsafe {
+                    libc::ioctl(fd.as_raw_fd(), request as _, 0);
+                }
+            }
+        }
+    }
+
+    fn open_event(event: Event) -> io::Result<OwnedFd> {
+        let (type_, config) = match event {
+            Event::Cycles => (PERF_TYPE_HARDWARE, PERF_COUNT_HW_CPU_CYCLES),
+            Event::Instructions => (PERF_TYPE_HARDWARE, PERF_COUNT_HW_INSTRUCTIONS),
+            Event::BranchMisses => (PERF_TYPE_HARDWARE, PERF_COUNT_HW_BRANCH_MISSES),
+            Event::CacheMisses => (PERF_TYPE_HARDWARE, PERF_COUNT_HW_CACHE_MISSES),
+            Event::TaskClock => (PERF_TYPE_SOFTWARE, PERF_COUNT_SW_TASK_CLOCK),
+        };
+        let attr = PerfEventAttr {
+            type_,
+            size: std::mem::size_of::<PerfEventAttr>() as u32,
+            config,
+            read_format: PERF_FORMAT_TOTAL_TIME_ENABLED | PERF_FORMAT_TOTAL_TIME_RUNNING,
+            flags: ATTR_DISABLED | ATTR_INHERIT | ATTR_EXCLUDE_KERNEL | ATTR_EXCLUDE_HV,
+            ..Default::default()
+        };
+        // SAFETY: `attr` is a fully initialised perf_event_attr that outlives
+        // the call; pid 0 / cpu -1 means this thread on any CPU.
+        let fd = unsafe {
+            libc::syscall(
+                libc::SYS_perf_event_open,
+                &attr as *const PerfEventAttr,
+                0 as libc::pid_t,
+                -1 as libc::c_int,
+                -1 as libc::c_int,
+                PERF_FLAG_FD_CLOEXEC,
+            )
+        };
+        if fd < 0 {
+            return Err(io::Error::last_os_error());
+        }
+        // SAFETY: the syscall returned a new file descriptor we now own.
+        Ok(unsafe { OwnedFd::from_raw_fd(fd as libc::c_int) })
+    }
+}
+
+#[cfg(not(target_os = "linux"))]
+mod sys {
+    use std::io;
+
+    use super::{Counters, Event};
+
+    /// Stand-in for platforms without `perf_event_open`: nothing opens.
+    pub struct PerfCounters {
+        pub unavailable: Vec<(Event, io::Error)>,
+    }
+
+    impl PerfCounters {
+        pub fn open() -> PerfCounters {
+            let unavailable = Event::ALL
+                .iter()
+                .map(|&e| (e, io::Error::from(io::ErrorKind::Unsupported)))
+                .collect();
+            PerfCounters { unavailable }
+        }
+
+        pub fn reset(&self) {}
+
+        pub fn enable(&self) {}
+
+        pub fn disable(&self) {}
+
+        pub fn read(&self, _runs: usize) -> Counters {
+            Counters::default()
+        }
+    }
+}
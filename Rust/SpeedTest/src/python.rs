@@ -0,0 +1,76 @@
+//! The kernels of the Python programs, run in an embedded CPython so they
+//! are timed by the runner's clock without interpreter startup.
+//!
+//! The `.py` files are compiled into the binary and loaded as modules on
+//! first use; their `main`s stay behind the `__name__` guard.
+
+use std::ffi::CString;
+
+use pyo3::prelude::*;
+use pyo3::sync::PyOnceLock;
+
+/// The kernel functions, looked up once.
+struct Kernels {
+    loop_sum: Py<PyAny>,
+    count_quad_res: Py<PyAny>,
+    count_inside_circle: Py<PyAny>,
+    seed: Py<PyAny>,
+}
+
+static KERNELS: PyOnceLock<Kernels> = PyOnceLock::new();
+
+const SOURCES: [(&str, &str); 3] = [
+    ("loop_test", include_str!("../loop_test.py")),
+    ("function_call", include_str!("../function_call.py")),
+    ("monte_carlo_pi", include_str!("../monte_carlo_pi.py")),
+];
+
+fn load(py: Python<'_>) -> PyResult<Kernels> {
+    let mut modules = Vec::new();
+    for (name, source) in SOURCES {
+        let code = CString::new(source).expect("no NUL in the Python sources");
+        let file = CString::new(format!("{}.py", name)).unwrap();
+        let name = CString::new(name).unwrap();
+        modules.push(PyModule::from_code(py, &code, &file, &name)?);
+    }
+    let random = py.import("random")?;
+    Ok(Kernels {
+        loop_sum: modules[0].getattr("loop_sum")?.unbind(),
+        count_quad_res: modules[1].getattr("count_quad_res")?.unbind(),
+        count_inside_circle: modules[2].getattr("count_inside_circle")?.unbind(),
+        seed: random.getattr("seed")?.unbind(),
+    })
+}
+
+/// Runs `f` with the kernels, panicking with the Python traceback on error.
+fn with_kernels<T>(f: impl FnOnce(Python<'_>, &Kernels) -> PyResult<T>) -> T {
+    Python::attach(|py| {
+        KERNELS
+            .get_or_try_init(py, || load(py))
+            .and_then(|kernels| f(py, kernels))
+            .unwrap_or_else(|e| {
+                e.print(py);
+                panic!("Python kernel failed: {}", e)
+            })
+    })
+}
+
+/// `loop_sum` from `loop_test.py`.
+pub fn py_loop_sum(outer: i64, inner: i64, modulus: i64) -> i64 {
+    with_kernels(|py, k| k.loop_sum.call1(py, (outer, inner, modulus))?.extract(py))
+}
+
+/// `count_quad_res` from `function_call.py`.
+pub fn py_count_quad_res(m: i64) -> i64 {
+    with_kernels(|py, k| k.count_quad_res.call1(py, (m,))?.extract(py))
+}
+
+/// `count_inside_circle` from `monte_carlo_pi.py`, after
+/// `random.seed(seed)`. This draws the same points as
+/// [`crate::rng::Mt19937::new`]`(seed)`.
+pub fn py_count_inside_circle(seed: u64, iterations: u64) -> u64 {
+    with_kernels(|py, k| {
+        k.seed.call1(py, (seed,))?;
+        k.count_inside_circle.call1(py, (iterations,))?.extract(py)
+    })
+}
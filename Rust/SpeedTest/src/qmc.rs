@@ -0,0 +1,379 @@
+//! Low-discrepancy point sets for quasi-Monte Carlo estimates of pi.
+//!
+//! Pseudo-random points converge at 1/sqrt(N); points that fill the square
+//! evenly do better, close to (log N)^2 / N. Two classic sequences are here:
+//! Halton, the radical inverse of the point index in two coprime bases, and
+//! two-dimensional Sobol, whose second coordinate uses the first entry of
+//! Joe and Kuo's direction-number table. Either can be Owen
+//! scrambled, which randomises the points while keeping them evenly spread.
+
+use std::fmt;
+use std::str::FromStr;
+
+use crate::rng::SplitMix64;
+
+/// A source of points in the unit square.
+pub trait PointSet {
+    fn next_point(&mut self) -> [f64; 2];
+}
+
+/// A kernel generic over the point set, the counterpart of
+/// [`crate::rng::RngFn`].
+pub trait PointFn<T> {
+    fn call<P: PointSet>(self, points: &mut P) -> T;
+}
+
+/// The low-discrepancy sequences.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Sequence {
+    Halton,
+    Sobol,
+}
+
+impl Sequence {
+    pub const ALL: [Sequence; 2] = [Sequence::Halton, Sequence::Sobol];
+
+    pub fn name(self) -> &'static str {
+        match self {
+            Sequence::Halton => "halton",
+            Sequence::Sobol => "sobol",
+        }
+    }
+}
+
+impl fmt::Display for Sequence {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.name())
+    }
+}
+
+impl FromStr for Sequence {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Sequence::ALL
+            .into_iter()
+            .find(|q| q.name() == s)
+            .ok_or_else(|| format!("unknown sequence `{}` (one of halton, sobol)", s))
+    }
+}
+
+/// Largest Halton base. Scrambling costs `base` hashes per digit.
+pub const MAX_BASE: u32 = 256;
+
+/// Sobol points are 32-bit, so the sequence has this many distinct points.
+pub const SOBOL_POINTS: u64 = 1 << 32;
+
+/// A fully specified point set.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Qmc {
+    pub sequence: Sequence,
+    /// Bases of the two Halton coordinates; ignored by Sobol.
+    pub bases: [u32; 2],
+    /// Apply a seeded Owen (nested uniform) scramble.
+    pub scramble: bool,
+}
+
+impl Default for Qmc {
+    fn default() -> Self {
+        Qmc {
+            sequence: Sequence::Sobol,
+            bases: [2, 3],
+            scramble: false,
+        }
+    }
+}
+
+impl Qmc {
+    /// Calls `f` with the point set, scrambled from `seed` if requested.
+    pub fn with_points<T>(self, seed: u64, f: impl PointFn<T>) -> T {
+        let seed = self.scramble.then_some(seed);
+        match self.sequence {
+            Sequence::Halton => f.call(&mut Halton::new(self.bases, seed)),
+            Sequence::Sobol => f.call(&mut Sobol::new(seed)),
+        }
+    }
+}
+
+/// `halton(2:3)` or `sobol`, with `+owen` when scrambled.
+impl fmt::Display for Qmc {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self.sequence {
+            Sequence::Halton => write!(f, "halton({})", format_bases(self.bases))?,
+            Sequence::Sobol => f.write_str("sobol")?,
+        }
+        if self.scramble {
+            f.write_str("+owen")?;
+        }
+        Ok(())
+    }
+}
+
+pub fn format_bases(bases: [u32; 2]) -> String {
+    format!("{}:{}", bases[0], bases[1])
+}
+
+/// Parses Halton bases written `2:3`. They must lie in `2..=`[`MAX_BASE`]
+/// and be coprime, or the two coordinates are correlated.
+pub fn parse_bases(s: &str) -> Result<[u32; 2], String> {
+    let (a, b) = s
+        .split_once(':')
+        .ok_or_else(|| format!("expected two bases like `2:3`, got `{}`", s))?;
+    let parse = |v: &str| -> Result<u32, String> {
+        let base: u32 = v.trim().parse().map_err(|e| format!("`{}`: {}", v, e))?;
+        if !(2..=MAX_BASE).contains(&base) {
+            return Err(format!("base {} is not between 2 and {}", base, MAX_BASE));
+        }
+        Ok(base)
+    };
+    let bases = [parse(a)?, parse(b)?];
+    if gcd(bases[0], bases[1]) != 1 {
+        return Err(format!("bases {} are not coprime", format_bases(bases)));
+    }
+    Ok(bases)
+}
+
+fn gcd(mut a: u32, mut b: u32) -> u32 {
+    while b != 0 {
+        (a, b) = (b, a % b);
+    }
+    a
+}
+
+/// How many of `iterations` points fall inside the quarter circle.
+pub fn count_inside_points<P: PointSet>(points: &mut P, iterations: u64) -> u64 {
+    let mut inside: u64 = 0;
+    for _ in 0..iterations {
+        let [x, y] = points.next_point();
+        if x * x + y * y <= 1.0 {
+            inside += 1;
+        }
+    }
+    inside
+}
+
+/// The Halton sequence: point `i` is the radical inverse of `i` in each base.
+pub struct Halton {
+    bases: [u32; 2],
+    index: u64,
+    /// Per-coordinate scramble seeds.
+    scramble: Option<[u64; 2]>,
+}
+
+impl Halton {
+    pub fn new(bases: [u32; 2], scramble: Option<u64>) -> Self {
+        let scramble = scramble.map(|seed| {
+            let mut sm = SplitMix64::new(seed);
+            [sm.next_u64(), sm.next_u64()]
+        });
+        Halton {
+            bases,
+            index: 0,
+            scramble,
+        }
+    }
+}
+
+impl PointSet for Halton {
+    #[inline]
+    fn next_point(&mut self) -> [f64; 2] {
+        let i = self.index;
+        self.index += 1;
+        match self.scramble {
+            None => [
+                radical_inverse(i, self.bases[0]),
+                radical_inverse(i, self.bases[1]),
+            ],
+            Some(seeds) => [
+                scrambled_radical_inverse(i, self.bases[0], seeds[0]),
+                scrambled_radical_inverse(i, self.bases[1], seeds[1]),
+            ],
+        }
+    }
+}
+
+/// The digits of `i` in base `b` mirrored about the radix point.
+#[inline]
+pub fn radical_inverse(mut i: u64, base: u32) -> f64 {
+    let b = base as u64;
+    let inv = 1.0 / base as f64;
+    let mut scale = inv;
+    let mut r = 0.0;
+    while i > 0 {
+        r += (i % b) as f64 * scale;
+        i /= b;
+        scale *= inv;
+    }
+    r
+}
+
+/// [`radical_inverse`] with Owen scrambling: each output digit goes through
+/// a random permutation of `0..base` chosen by the digits above it. The
+/// permutations come from hashing `seed` with that prefix, so the scramble
+/// needs no tables. Past the end of `i` every digit is a zero whose prefix no
+/// other index shares, so the scrambled tail is simply uniform below the
+/// last digit and is drawn in one go.
+pub fn scrambled_radical_inverse(mut i: u64, base: u32, seed: u64) -> f64 {
+    let b = base as u64;
+    let inv = 1.0 / base as f64;
+    let mut scale = 1.0;
+    let mut r = 0.0;
+    let mut node = seed;
+    while i > 0 {
+        let d = (i % b) as u32;
+        i /= b;
+        scale *= inv;
+        r += permute(d, base, node) as f64 * scale;
+        node =
+            SplitMix64::new(node ^ (d as u64 + 1).wrapping_mul(0xd6e8_feb8_6659_fd93)).next_u64();
+    }
+    let tail = (SplitMix64::new(node).next_u64() >> 11) as f64 * (1.0 / (1u64 << 53) as f64);
+    r + tail * scale
+}
+
+/// Where `d` lands under the random permutation of `0..base` seeded by
+/// `node`. Runs Fisher-Yates but follows only `d`, so no table is built;
+/// the swap positions come from an LCG on the node hash, taken by
+/// multiply-shift so only its strong high bits matter.
+#[inline]
+fn permute(d: u32, base: u32, node: u64) -> u32 {
+    let mut h = node;
+    let mut pos = d as u64;
+    for k in (1..base as u64).rev() {
+        h = h
+            .wrapping_mul(0x5851_f42d_4c95_7f2d)
+            .wrapping_add(0x1405_7b7e_f767_814f);
+        let j = ((h as u128 * (k + 1) as u128) >> 64) as u64;
+        if pos == k {
+            pos = j;
+        } else if pos == j {
+            pos = k;
+        }
+    }
+    pos as u32
+}
+
+/// The primitive polynomial and initial direction numbers of the second
+/// Sobol coordinate, as `(degree s, coefficients a, m_1..m_s)`: `x + 1` with
+/// `m_1 = 1`, the first line of Joe and Kuo's `new-joe-kuo-6.21201`. The
+/// first coordinate is the van der Corput sequence and needs none, and a
+/// two-dimensional sequence needs no more of the table.
+const DIMENSION_2: (u32, u32, &[u32]) = (1, 0, &[1]);
+
+/// The two-dimensional Sobol sequence in Gray-code order, which visits the
+/// same points as the natural order in each block of 2^k but updates a
+/// point with a single XOR.
+pub struct Sobol {
+    directions: [[u32; 32]; 2],
+    x: [u32; 2],
+    index: u64,
+    /// Per-coordinate Owen scramble seeds.
+    scramble: Option<[u32; 2]>,
+}
+
+impl Sobol {
+    pub fn new(scramble: Option<u64>) -> Self {
+        let van_der_corput = std::array::from_fn(|k| 1u32 << (31 - k));
+        let (s, a, m) = DIMENSION_2;
+        Sobol {
+            directions: [van_der_corput, directions(s, a, m)],
+            x: [0, 0],
+            index: 0,
+            scramble: scramble.map(|seed| {
+                let mut sm = SplitMix64::new(seed);
+                [sm.next_u64() as u32, sm.next_u64() as u32]
+            }),
+        }
+    }
+}
+
+/// Direction numbers `v_k = m_k / 2^k` as 32-bit fractions, from the
+/// recurrence of the primitive polynomial with degree `s` and inner
+/// coefficients `a`.
+fn directions(s: u32, a: u32, m: &[u32]) -> [u32; 32] {
+    let s = s as usize;
+    let mut v = [0u32; 32];
+    for k in 0..32 {
+        v[k] = if k < s {
+            m[k] << (31 - k)
+        } else {
+            let mut x = v[k - s] ^ (v[k - s] >> s);
+            for l in 1..s {
+                if (a >> (s - 1 - l)) & 1 == 1 {
+                    x ^= v[k - l];
+                }
+            }
+            x
+        };
+    }
+    v
+}
+
+impl PointSet for Sobol {
+    #[inline]
+    fn next_point(&mut self) -> [f64; 2] {
+        let mut x = self.x;
+        self.index += 1;
+        // Only the step past the last point would need a 33rd direction.
+        let c = self.index.trailing_zeros() as usize;
+        if c < 32 {
+            self.x[0] ^= self.directions[0][c];
+            self.x[1] ^= self.directions[1][c];
+        }
+        if let Some(seeds) = self.scramble {
+            x = [owen_scramble(x[0], seeds[0]), owen_scramble(x[1], seeds[1])];
+        }
+        x.map(|v| v as f64 * (1.0 / 4_294_967_296.0))
+    }
+}
+
+/// Owen scrambling of a base-2 fraction by Burley's hash ("Practical
+/// Hash-based Owen Scrambling", JCGT 2020): with the bits reversed, a
+/// Laine-Karras style hash flips each bit depending only on the bits
+/// below it, which is a nested uniform scramble of the original.
+#[inline]
+pub fn owen_scramble(x: u32, seed: u32) -> u32 {
+    let mut v = x.reverse_bits();
+    v = v.wrapping_add(seed);
+    v ^= v.wrapping_mul(0x6c50_b47c);
+    v ^= v.wrapping_mul(0xb82f_1e52);
+    v ^= v.wrapping_mul(0xc7af_e638);
+    v ^= v.wrapping_mul(0x8d22_f6e6);
+    v.reverse_bits()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Whether the points put exactly one point in every box of `2^-k1` by
+    /// `2^-k2`, for every split of `k = k1 + k2`. With `k2 = 0` or `k1 = 0`
+    /// that is each coordinate on its own being a (0,k,1)-net.
+    fn is_0k2_net(points: &[[f64; 2]], k: u32) -> bool {
+        (0..=k).all(|k1| {
+            let k2 = k - k1;
+            let mut seen = vec![false; 1 << k];
+            points.iter().all(|&[x, y]| {
+                let cell =
+                    ((x * (1u64 << k1) as f64) as usize) << k2 | (y * (1u64 << k2) as f64) as usize;
+                !std::mem::replace(&mut seen[cell], true)
+            })
+        })
+    }
+
+    #[test]
+    fn sobol_points_form_0k_nets() {
+        for scramble in [None, Some(11)] {
+            let mut sobol = Sobol::new(scramble);
+            let points: Vec<[f64; 2]> = (0..1 << 12).map(|_| sobol.next_point()).collect();
+            for k in 0..=12 {
+                assert!(
+                    is_0k2_net(&points[..1 << k], k),
+                    "first 2^{} points, scramble {:?}",
+                    k,
+                    scramble
+                );
+            }
+        }
+    }
+}
@@ -0,0 +1,414 @@
+//! Turning measurements into table, JSON or CSV output.
+
+use std::collections::BTreeMap;
+use std::io::{self, Write};
+
+use serde::{Deserialize, Serialize};
+
+use crate::environment::{cpu_list, Environment};
+use crate::host::HostInfo;
+use crate::memory::{format_bytes, MemoryUsage};
+use crate::perf::Counters;
+use crate::stats::{format_ns, Summary};
+use crate::{Benchmark, Measurement, RunConfig, Uncertainty, Value};
+
+/// How results are written to stdout.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, clap::ValueEnum)]
+pub enum Format {
+    /// Human-readable text.
+    Table,
+    /// One JSON object per benchmark per line (JSON Lines).
+    Json,
+    /// A header row followed by one row per benchmark.
+    Csv,
+}
+
+/// Everything known about one benchmark's measurement.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Record {
+    pub benchmark: String,
+    pub params: BTreeMap<String, String>,
+    pub result: Value,
+    /// Whether the result matched the benchmark's oracle.
+    pub verified: bool,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub verify_error: Option<String>,
+    /// Standard error and confidence interval of `result`, for tests that
+    /// estimate their answer by sampling.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub uncertainty: Option<Uncertainty>,
+    pub warmup: usize,
+    pub timing: Summary,
+    pub samples_ns: Vec<u64>,
+    /// Units of work in one run, see [`Benchmark::work`].
+    #[serde(default)]
+    pub work: f64,
+    /// What a unit of work is, when the time per unit is reported.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub work_unit: Option<String>,
+    /// The test this one is a variant of, see [`Benchmark::reference`].
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub reference: Option<String>,
+    /// Median time per unit of work divided by the reference test's, when
+    /// that ran first in the same session.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub relative_cost: Option<f64>,
+    /// Per-run hardware counter averages, when collected.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub counters: Option<Counters>,
+    /// Memory use over the timed runs, when collected.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub memory: Option<MemoryUsage>,
+    pub host: HostInfo,
+    /// System conditions captured just before the benchmark ran.
+    #[serde(default)]
+    pub environment: Environment,
+}
+
+impl Record {
+    pub fn new(
+        bench: &dyn Benchmark,
+        config: &RunConfig,
+        m: &Measurement,
+        host: &HostInfo,
+        environment: &Environment,
+    ) -> Record {
+        let verify_error = bench.expected().check(m.value).err();
+        Record {
+            benchmark: bench.name().to_string(),
+            params: bench
+                .params()
+                .into_iter()
+                .map(|(k, v)| (k.to_string(), v))
+                .collect(),
+            result: m.value,
+            verified: verify_error.is_none(),
+            verify_error,
+            uncertainty: bench.uncertainty(m.value),
+            warmup: config.warmup,
+            timing: m.summary,
+            samples_ns: m.samples.iter().map(|d| d.as_nanos() as u64).collect(),
+            work: bench.work(),
+            work_unit: bench.work_unit().map(String::from),
+            reference: bench.reference().map(String::from),
+            relative_cost: None,
+            counters: m.counters,
+            memory: m.memory,
+            host: host.clone(),
+            environment: environment.clone(),
+        }
+    }
+
+    /// Median nanoseconds per unit of work, for benchmarks with a
+    /// [`Benchmark::work_unit`].
+    pub fn ns_per_unit(&self) -> Option<f64> {
+        self.work_unit
+            .as_ref()
+            .filter(|_| self.work > 0.0)
+            .map(|_| self.timing.median / self.work)
+    }
+
+    /// Sets [`Record::relative_cost`] from the reference test's record.
+    pub fn compare_to_reference(&mut self, reference: &Record) {
+        if self.work > 0.0 && reference.work > 0.0 && reference.timing.median > 0.0 {
+            self.relative_cost =
+                Some((self.timing.median / self.work) / (reference.timing.median / reference.work));
+        }
+    }
+
+    fn params_string(&self) -> String {
+        self.params
+            .iter()
+            .map(|(k, v)| format!("{}={}", k, v))
+            .collect::<Vec<_>>()
+            .join(" ")
+    }
+}
+
+const CSV_HEADER: &[&str] = &[
+    "benchmark",
+    "params",
+    "result",
+    "verified",
+    "std_error",
+    "ci95_low",
+    "ci95_high",
+    "warmup",
+    "runs",
+    "min_ns",
+    "median_ns",
+    "mean_ns",
+    "stddev_ns",
+    "ci95_ns",
+    "mad_ns",
+    "outliers",
+    "work",
+    "work_unit",
+    "ns_per_unit",
+    "reference",
+    "relative_cost",
+    "cycles",
+    "instructions",
+    "ipc",
+    "branch_misses",
+    "cache_misses",
+    "task_clock_ns",
+    "rss_bytes",
+    "peak_rss_bytes",
+    "minor_faults",
+    "major_faults",
+    "allocations",
+    "allocated_bytes",
+    "peak_heap_bytes",
+    "allocates_in_loop",
+    "hostname",
+    "cpu_model",
+    "kernel",
+    "rustc",
+    "governor",
+    "cur_mhz",
+    "load1",
+    "max_temp_c",
+    "affinity",
+];
+
+/// Writes records in the chosen format as they arrive.
+pub struct Reporter<W: Write> {
+    format: Format,
+    out: W,
+    wrote_header: bool,
+}
+
+impl<W: Write> Reporter<W> {
+    pub fn new(format: Format, out: W) -> Self {
+        Reporter {
+            format,
+            out,
+            wrote_header: false,
+        }
+    }
+
+    pub fn write(&mut self, record: &Record) -> io::Result<()> {
+        match self.format {
+            Format::Table => self.write_table(record),
+            Format::Json => {
+                serde_json::to_writer(&mut self.out, record)?;
+                writeln!(self.out)
+            }
+            Format::Csv => self.write_csv(record),
+        }
+    }
+
+    fn write_table(&mut self, r: &Record) -> io::Result<()> {
+        let s = &r.timing;
+        let out = &mut self.out;
+        writeln!(out, "{} ({})", r.benchmark, r.params_string())?;
+        match &r.verify_error {
+            None => writeln!(out, "  result  {} (verified)", r.result)?,
+            Some(e) => writeln!(out, "  result  {} WRONG: {}", r.result, e)?,
+        }
+        if let Some(u) = &r.uncertainty {
+            writeln!(
+                out,
+                "  stderr  {:.6}, 95% CI [{:.6}, {:.6}]",
+                u.std_error, u.ci95_low, u.ci95_high
+            )?;
+        }
+        writeln!(out, "  min     {}", format_ns(s.min))?;
+        writeln!(out, "  median  {}", format_ns(s.median))?;
+        writeln!(
+            out,
+            "  mean    {} +/- {}",
+            format_ns(s.mean),
+            format_ns(s.stddev)
+        )?;
+        writeln!(
+            out,
+            "  95% CI  [{}, {}]",
+            format_ns(s.mean - s.ci95),
+            format_ns(s.mean + s.ci95)
+        )?;
+        if s.outliers > 0 {
+            writeln!(
+                out,
+                "  {} of {} runs are outliers (MAD {})",
+                s.outliers,
+                s.runs,
+                format_ns(s.mad)
+            )?;
+        }
+        if let (Some(unit), Some(ns)) = (&r.work_unit, r.ns_per_unit()) {
+            // Per-unit times are often below a nanosecond, too fine for
+            // `format_ns`.
+            let per = if ns < 1e3 {
+                format!("{:.3} ns", ns)
+            } else {
+                format_ns(ns)
+            };
+            writeln!(out, "  per {}  {}", unit, per)?;
+        }
+        if let (Some(reference), Some(cost)) = (&r.reference, r.relative_cost) {
+            writeln!(
+                out,
+                "  vs {}  {:.2}x the time per unit of work",
+                reference, cost
+            )?;
+        }
+        let env = r.environment.summary();
+        if !env.is_empty() {
+            writeln!(out, "  env     {}", env)?;
+        }
+        if let Some(c) = &r.counters {
+            writeln!(out, "  per run   {}", counters_line(c))?;
+            if let Some(ipc) = c.ipc() {
+                writeln!(out, "  IPC       {:.3}", ipc)?;
+            }
+            if r.work > 0.0 {
+                writeln!(out, "  per work  {}", counters_line(&c.per_work(r.work)))?;
+            }
+        }
+        if let Some(m) = &r.memory {
+            write_memory(out, m)?;
+        }
+        Ok(())
+    }
+
+    fn write_csv(&mut self, r: &Record) -> io::Result<()> {
+        if !self.wrote_header {
+            writeln!(self.out, "{}", CSV_HEADER.join(","))?;
+            self.wrote_header = true;
+        }
+        let s = &r.timing;
+        let c = r.counters.unwrap_or_default();
+        let mem = r.memory.unwrap_or_default();
+        let heap = mem.heap;
+        let env = &r.environment;
+        let freq = env.active_cpufreq().next();
+        let u = r.uncertainty;
+        let fields = [
+            r.benchmark.clone(),
+            r.params_string(),
+            match r.result {
+                Value::Int(v) => v.to_string(),
+                Value::Float(v) => v.to_string(),
+            },
+            r.verified.to_string(),
+            opt(u.map(|u| u.std_error), 9),
+            opt(u.map(|u| u.ci95_low), 9),
+            opt(u.map(|u| u.ci95_high), 9),
+            r.warmup.to_string(),
+            s.runs.to_string(),
+            format!("{:.0}", s.min),
+            format!("{:.0}", s.median),
+            format!("{:.1}", s.mean),
+            format!("{:.1}", s.stddev),
+            format!("{:.1}", s.ci95),
+            format!("{:.1}", s.mad),
+            s.outliers.to_string(),
+            format!("{:.0}", r.work),
+            r.work_unit.clone().unwrap_or_default(),
+            opt(r.ns_per_unit(), 3),
+            r.reference.clone().unwrap_or_default(),
+            opt(r.relative_cost, 3),
+            opt(c.cycles, 0),
+            opt(c.instructions, 0),
+            opt(c.ipc(), 3),
+            opt(c.branch_misses, 0),
+            opt(c.cache_misses, 0),
+            opt(c.task_clock_ns, 0),
+            opt(mem.rss_bytes.map(|b| b as f64), 0),
+            opt(mem.peak_rss_bytes.map(|b| b as f64), 0),
+            opt(mem.minor_faults, 1),
+            opt(mem.major_faults, 1),
+            opt(heap.map(|h| h.allocations), 1),
+            opt(heap.map(|h| h.bytes), 0),
+            opt(heap.map(|h| h.peak_bytes as f64), 0),
+            heap.map(|h| h.allocates_in_loop.to_string())
+                .unwrap_or_default(),
+            r.host.hostname.clone(),
+            r.host.cpu_model.clone(),
+            r.host.kernel.clone(),
+            r.host.rustc.clone(),
+            freq.and_then(|f| f.governor.clone()).unwrap_or_default(),
+            opt(freq.and_then(|f| f.cur_khz).map(|k| k as f64 / 1000.0), 0),
+            opt(env.load_avg.map(|l| l[0]), 2),
+            opt(env.max_temp_celsius(), 1),
+            cpu_list(&env.affinity),
+        ];
+        let row: Vec<String> = fields.iter().map(|f| csv_field(f)).collect();
+        writeln!(self.out, "{}", row.join(","))
+    }
+}
+
+fn write_memory(out: &mut impl Write, m: &MemoryUsage) -> io::Result<()> {
+    let bytes = |b: Option<u64>| b.map_or_else(|| "?".to_string(), |b| format_bytes(b as f64));
+    writeln!(
+        out,
+        "  RSS     {} before, peak {}{}",
+        bytes(m.rss_bytes),
+        bytes(m.peak_rss_bytes),
+        if m.peak_rss_reset {
+            ""
+        } else {
+            " (whole process)"
+        }
+    )?;
+    if let (Some(minor), Some(major)) = (m.minor_faults, m.major_faults) {
+        writeln!(
+            out,
+            "  faults  {:.1} minor, {:.1} major per run",
+            minor, major
+        )?;
+    }
+    if let Some(h) = &m.heap {
+        writeln!(
+            out,
+            "  heap    {:.1} allocations, {} per run, peak {}{}",
+            h.allocations,
+            format_bytes(h.bytes),
+            format_bytes(h.peak_bytes as f64),
+            if h.allocates_in_loop {
+                ", ALLOCATES IN HOT LOOP"
+            } else {
+                ""
+            }
+        )?;
+    }
+    Ok(())
+}
+
+fn counters_line(c: &Counters) -> String {
+    [
+        ("cycles", c.cycles),
+        ("instructions", c.instructions),
+        ("branch-misses", c.branch_misses),
+        ("cache-misses", c.cache_misses),
+        ("task-clock-ns", c.task_clock_ns),
+    ]
+    .iter()
+    .filter_map(|(name, v)| v.map(|v| format!("{} {}", name, short_number(v))))
+    .collect::<Vec<_>>()
+    .join(", ")
+}
+
+/// Three significant digits, switching to exponent form for large values.
+fn short_number(v: f64) -> String {
+    if v != 0.0 && !(0.001..1e6).contains(&v.abs()) {
+        format!("{:.3e}", v)
+    } else {
+        format!("{:.3}", v)
+    }
+}
+
+fn opt(v: Option<f64>, decimals: usize) -> String {
+    v.map(|v| format!("{:.*}", decimals, v)).unwrap_or_default()
+}
+
+/// Quotes a CSV field when it contains a separator, quote or newline.
+fn csv_field(s: &str) -> String {
+    if s.contains([',', '"', '\n']) {
+        format!("\"{}\"", s.replace('"', "\"\""))
+    } else {
+        s.to_string()
+    }
+}
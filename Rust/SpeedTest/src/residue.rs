@@ -0,0 +1,716 @@
+//! Quadratic residues: faster alternatives to the naive `quad_res`, and a
+//! checked API for any modulus that fits in a `u64`.
+//!
+//! `n` is a quadratic residue mod `m` when `i*i ≡ n (mod m)` for some `i`,
+//! counting `0` as in the original programs. The naive test tries every `i`;
+//! the methods here are
+//!
+//! - a sieve that marks all squares mod `m` in one pass,
+//! - Euler's criterion `n^((p-1)/2) ≡ 1 (mod p)` for prime `p`,
+//! - the Legendre symbol via the Jacobi symbol's reciprocity algorithm, also
+//!   for prime `p`,
+//! - for composite `m`, the Chinese remainder theorem: `n` is a residue mod
+//!   `m` exactly when it is one mod every prime power dividing `m`, and
+//!   residues mod a prime power reduce to a Legendre symbol.
+//!
+//! The square roots themselves follow the same path: Tonelli-Shanks mod a
+//! prime, Hensel lifting to prime powers, and CRT to combine them.
+//!
+//! [`Modulus`] wraps the CRT test with the input handling of
+//! `function_call.c/.py`: `n` is reduced with `(n % m + m) % m`, so negative
+//! `n` work, and a modulus that is not positive is an error rather than the
+//! programs' silent `0`. Products are widened to 128 bits when `m` exceeds
+//! 2^32, so every modulus up to `u64::MAX` is exact.
+
+use std::fmt;
+
+use crate::benches::function_call::quad_res;
+
+/// Why a modulus was rejected.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ResidueError {
+    /// Zero or negative; `quad_res` in C and Python returns 0 for these.
+    NonPositiveModulus(i128),
+    /// Larger than `u64::MAX`.
+    ModulusTooLarge(i128),
+    /// More square roots than [`MAX_ROOTS`] to list; [`sqrt_count`] gives
+    /// the number without listing them.
+    TooManyRoots(u64),
+}
+
+impl fmt::Display for ResidueError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ResidueError::NonPositiveModulus(m) => {
+                write!(f, "modulus {} is not positive", m)
+            }
+            ResidueError::ModulusTooLarge(m) => {
+                write!(f, "modulus {} does not fit in 64 bits", m)
+            }
+            ResidueError::TooManyRoots(count) => {
+                write!(
+                    f,
+                    "{} square roots are too many to list (at most {})",
+                    count, MAX_ROOTS
+                )
+            }
+        }
+    }
+}
+
+impl std::error::Error for ResidueError {}
+
+/// A valid modulus, `1..=u64::MAX`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct Modulus(u64);
+
+impl Modulus {
+    /// Accepts any primitive integer type, so both `i64` moduli from the C
+    /// port and `u64` moduli up to `u64::MAX` work.
+    pub fn new(m: impl Into<i128>) -> Result<Modulus, ResidueError> {
+        let m = m.into();
+        if m <= 0 {
+            return Err(ResidueError::NonPositiveModulus(m));
+        }
+        u64::try_from(m)
+            .map(Modulus)
+            .map_err(|_| ResidueError::ModulusTooLarge(m))
+    }
+
+    pub fn get(self) -> u64 {
+        self.0
+    }
+
+    /// `n` reduced into `0..m` as `(n % m + m) % m` does in C and Python.
+    pub fn reduce(self, n: impl Into<i128>) -> u64 {
+        n.into().rem_euclid(self.0 as i128) as u64
+    }
+
+    /// Whether `n` is a quadratic residue, by factorising the modulus and
+    /// applying the CRT.
+    pub fn is_residue(self, n: impl Into<i128>) -> bool {
+        is_residue_crt(self.reduce(n), &factorize(self.0))
+    }
+
+    /// The square roots of `n`, ascending; empty for a non-residue. Fails
+    /// when there are more than [`MAX_ROOTS`].
+    pub fn sqrt(self, n: impl Into<i128>) -> Result<Vec<u64>, ResidueError> {
+        sqrt_mod_crt(self.reduce(n), &factorize(self.0))
+    }
+
+    /// The number of square roots of `n`, without listing them.
+    pub fn sqrt_count(self, n: impl Into<i128>) -> u64 {
+        sqrt_count(self.reduce(n), &factorize(self.0))
+    }
+
+    /// The number of residues, from [`count_closed_form`].
+    pub fn count(self) -> u64 {
+        count_closed_form(self.0)
+    }
+}
+
+impl fmt::Display for Modulus {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.0)
+    }
+}
+
+/// The checked counterpart of `quad_res(n, m)`: any integer `n`, and any
+/// modulus in `1..=u64::MAX`.
+pub fn is_quadratic_residue(n: impl Into<i128>, m: impl Into<i128>) -> Result<bool, ResidueError> {
+    Ok(Modulus::new(m)?.is_residue(n))
+}
+
+/// `a * b mod m` for `a, b < m`. The product fits in 64 bits while `m` is at
+/// most 2^32; beyond that it is widened to 128 bits.
+#[inline]
+pub fn mul_mod(a: u64, b: u64, m: u64) -> u64 {
+    if m <= 1 << 32 {
+        a * b % m
+    } else {
+        (a as u128 * b as u128 % m as u128) as u64
+    }
+}
+
+/// `a + b mod m` for `a, b < m`, without overflowing near `u64::MAX`.
+#[inline]
+fn add_mod(a: u64, b: u64, m: u64) -> u64 {
+    if a >= m - b {
+        a - (m - b)
+    } else {
+        a + b
+    }
+}
+
+/// `base^exp mod m`.
+pub fn pow_mod(base: u64, mut exp: u64, m: u64) -> u64 {
+    let mut base = base % m;
+    let mut result = 1 % m;
+    while exp > 0 {
+        if exp & 1 == 1 {
+            result = mul_mod(result, base, m);
+        }
+        base = mul_mod(base, base, m);
+        exp >>= 1;
+    }
+    result
+}
+
+/// Marks which `n` in `0..m` are squares mod `m`, by walking `i*i % m` for
+/// `i` up to `m/2` (the rest mirror these) with the difference
+/// `(i+1)^2 - i^2 = 2i + 1` instead of a multiplication.
+pub fn sieve(m: u64) -> Vec<bool> {
+    let mut is_square = vec![false; m as usize];
+    let mut square = 0;
+    for i in 0..=m / 2 {
+        is_square[square as usize] = true;
+        square = add_mod(square, (2 * i + 1) % m, m);
+    }
+    is_square
+}
+
+pub fn count_sieve(m: u64) -> u64 {
+    sieve(m).iter().filter(|&&s| s).count() as u64
+}
+
+/// Euler's criterion for prime `p`: `n` is a residue exactly when
+/// `n^((p-1)/2) mod p` is 0 or 1.
+pub fn is_residue_euler(n: u64, p: u64) -> bool {
+    if p == 2 {
+        return true;
+    }
+    pow_mod(n, (p - 1) / 2, p) <= 1
+}
+
+/// The Jacobi symbol `(n/m)` for odd `m`, by quadratic reciprocity: pull out
+/// factors of two, flip, reduce, repeat. For prime `m` it is the Legendre
+/// symbol.
+pub fn jacobi(n: u64, m: u64) -> i32 {
+    debug_assert!(m % 2 == 1, "the Jacobi symbol needs odd m");
+    let mut a = n % m;
+    let mut m = m;
+    let mut result = 1;
+    while a != 0 {
+        while a.is_multiple_of(2) {
+            a /= 2;
+            // (2/m) = -1 when m ≡ 3 or 5 (mod 8).
+            if m % 8 == 3 || m % 8 == 5 {
+                result = -result;
+            }
+        }
+        std::mem::swap(&mut a, &mut m);
+        // (a/m)(m/a) = -1 when both are ≡ 3 (mod 4).
+        if a % 4 == 3 && m % 4 == 3 {
+            result = -result;
+        }
+        a %= m;
+    }
+    if m == 1 {
+        result
+    } else {
+        0
+    }
+}
+
+/// The Legendre symbol test for prime `p`, computed as a Jacobi symbol.
+pub fn is_residue_jacobi(n: u64, p: u64) -> bool {
+    if p == 2 {
+        return true;
+    }
+    jacobi(n, p) >= 0
+}
+
+/// Deterministic Miller-Rabin; these bases decide every `u64`.
+pub fn is_prime(m: u64) -> bool {
+    const BASES: [u64; 12] = [2, 3, 5, 7, 11, 13, 17, 19, 23, 29, 31, 37];
+    if m < 2 {
+        return false;
+    }
+    for p in BASES {
+        if m.is_multiple_of(p) {
+            return m == p;
+        }
+    }
+    let s = (m - 1).trailing_zeros();
+    let d = (m - 1) >> s;
+    BASES.iter().all(|&a| {
+        let mut x = pow_mod(a, d, m);
+        if x == 1 || x == m - 1 {
+            return true;
+        }
+        for _ in 1..s {
+            x = mul_mod(x, x, m);
+            if x == m - 1 {
+                return true;
+            }
+        }
+        false
+    })
+}
+
+/// The prime factors of `m` with their exponents, smallest first: trial
+/// division by small primes, then Pollard's rho for what is left.
+pub fn factorize(mut m: u64) -> Vec<(u64, u32)> {
+    let mut primes = Vec::new();
+    let mut p = 2;
+    while p < 1000 && p * p <= m {
+        while m.is_multiple_of(p) {
+            m /= p;
+            primes.push(p);
+        }
+        p += if p == 2 { 1 } else { 2 };
+    }
+    if m > 1 {
+        split(m, &mut primes);
+    }
+    primes.sort_unstable();
+    let mut factors: Vec<(u64, u32)> = Vec::new();
+    for p in primes {
+        match factors.last_mut() {
+            Some((q, k)) if *q == p => *k += 1,
+            _ => factors.push((p, 1)),
+        }
+    }
+    factors
+}
+
+/// Appends the prime factors of `m`, which has none below 1000.
+fn split(m: u64, primes: &mut Vec<u64>) {
+    if m == 1 {
+        return;
+    }
+    if is_prime(m) {
+        primes.push(m);
+        return;
+    }
+    let d = pollard_rho(m);
+    split(d, primes);
+    split(m / d, primes);
+}
+
+/// A nontrivial factor of the odd composite `m`, by Pollard's rho with
+/// Floyd cycle detection on `x -> x^2 + c`.
+fn pollard_rho(m: u64) -> u64 {
+    for c in 1.. {
+        let f = |x: u64| add_mod(mul_mod(x, x, m), c, m);
+        let (mut x, mut y, mut d) = (2, 2, 1);
+        while d == 1 {
+            x = f(x);
+            y = f(f(y));
+            d = gcd(x.abs_diff(y), m);
+        }
+        if d != m {
+            return d;
+        }
+    }
+    unreachable!("some c splits every composite")
+}
+
+fn gcd(mut a: u64, mut b: u64) -> u64 {
+    while b != 0 {
+        (a, b) = (b, a % b);
+    }
+    a
+}
+
+/// Whether `n` is a residue mod `p^k`. Writing `n = p^e * u` with `u` prime
+/// to `p`, a nonzero `n` is a residue exactly when `e` is even and `u` is a
+/// residue mod `p^(k-e)`. For odd `p` that means `u` is a residue mod `p`;
+/// for `p = 2` it means `u ≡ 1` mod 2, 4 or 8 as `k - e` is 1, 2 or more.
+pub fn is_residue_prime_power(n: u64, p: u64, k: u32) -> bool {
+    let pk = p.pow(k);
+    let mut u = n % pk;
+    if u == 0 {
+        return true;
+    }
+    let mut e = 0;
+    while u.is_multiple_of(p) {
+        u /= p;
+        e += 1;
+    }
+    if e % 2 == 1 {
+        return false;
+    }
+    if p == 2 {
+        match k - e {
+            1 => true,
+            2 => u % 4 == 1,
+            _ => u % 8 == 1,
+        }
+    } else {
+        is_residue_jacobi(u, p)
+    }
+}
+
+/// The CRT test: `n` is a residue mod `m` exactly when it is one modulo
+/// every prime power in `factors`, the factorisation of `m`.
+pub fn is_residue_crt(n: u64, factors: &[(u64, u32)]) -> bool {
+    factors
+        .iter()
+        .all(|&(p, k)| is_residue_prime_power(n, p, k))
+}
+
+/// The quadratic residues mod `m` in ascending order, read off [`sieve`].
+pub fn residues(m: u64) -> Vec<u64> {
+    sieve(m)
+        .iter()
+        .enumerate()
+        .filter(|&(_, &s)| s)
+        .map(|(n, _)| n as u64)
+        .collect()
+}
+
+/// `a - b mod m` for `a, b < m`.
+#[inline]
+fn sub_mod(a: u64, b: u64, m: u64) -> u64 {
+    if a >= b {
+        a - b
+    } else {
+        a + (m - b)
+    }
+}
+
+/// The inverse of `a` mod `m` by the extended Euclidean algorithm, if `a`
+/// is prime to `m`.
+pub fn inverse_mod(a: u64, m: u64) -> Option<u64> {
+    let (mut r0, mut r1) = (m as i128, (a % m) as i128);
+    let (mut t0, mut t1) = (0i128, 1i128);
+    while r1 != 0 {
+        let q = r0 / r1;
+        (r0, r1) = (r1, r0 - q * r1);
+        (t0, t1) = (t1, t0 - q * t1);
+    }
+    (r0 == 1).then(|| t0.rem_euclid(m as i128) as u64)
+}
+
+/// A square root of `n` mod prime `p` by Tonelli-Shanks, or `None` for a
+/// non-residue. The other root is `p - r`.
+///
+/// Write `p - 1 = q * 2^s` with `q` odd. Starting from `x = n^((q+1)/2)`,
+/// `x^2 = n * t` where `t = n^q` has order dividing `2^s`; each step
+/// multiplies `x` by a power of `z^q`, for a non-residue `z`, that halves
+/// the order of `t` until `t = 1`.
+pub fn sqrt_mod_prime(n: u64, p: u64) -> Option<u64> {
+    let n = n % p;
+    if p == 2 || n == 0 {
+        return Some(n);
+    }
+    if !is_residue_euler(n, p) {
+        return None;
+    }
+    let s = (p - 1).trailing_zeros();
+    let q = (p - 1) >> s;
+    let z = (2..).find(|&z| !is_residue_jacobi(z, p)).unwrap();
+    let mut c = pow_mod(z, q, p);
+    let mut x = pow_mod(n, q.div_ceil(2), p);
+    let mut t = pow_mod(n, q, p);
+    let mut order = s;
+    while t != 1 {
+        // The least i with t^(2^i) = 1.
+        let mut i = 0;
+        let mut t2 = t;
+        while t2 != 1 {
+            t2 = mul_mod(t2, t2, p);
+            i += 1;
+        }
+        let b = pow_mod(c, 1 << (order - i - 1), p);
+        x = mul_mod(x, b, p);
+        c = mul_mod(b, b, p);
+        t = mul_mod(t, c, p);
+        order = i;
+    }
+    Some(x)
+}
+
+/// The square roots mod `p^e` of `u`, which is prime to `p`. For odd `p` a
+/// root mod `p` is Hensel-lifted one power at a time by Newton's step
+/// `r -> r - (r^2 - u) / 2r`, giving the pair `±r`. For `p = 2` a root mod
+/// `2^i` is `r` or `r + 2^(i-1)` mod `2^(i+1)`, and from `2^3` on there are
+/// four, `±r` and `±r + 2^(e-1)`.
+fn unit_roots(u: u64, p: u64, e: u32) -> Vec<u64> {
+    let pe = p.pow(e);
+    let mut roots = if p == 2 {
+        match e {
+            0 => vec![0],
+            1 => vec![1],
+            2 if u % 4 == 1 => vec![1, 3],
+            2 => vec![],
+            _ if u % 8 != 1 => vec![],
+            _ => {
+                let mut r = 1;
+                for i in 3..e {
+                    let next = 1 << (i + 1);
+                    if mul_mod(r, r, next) != u % next {
+                        r += 1 << (i - 1);
+                    }
+                }
+                let half = pe / 2;
+                vec![r, pe - r, (r + half) % pe, (pe - r + half) % pe]
+            }
+        }
+    } else {
+        let Some(mut r) = sqrt_mod_prime(u, p) else {
+            return vec![];
+        };
+        let mut pi = p;
+        for _ in 1..e {
+            pi *= p;
+            let f = sub_mod(mul_mod(r, r, pi), u % pi, pi);
+            let inv = inverse_mod(2 * r % pi, pi).expect("2r is prime to odd p");
+            r = sub_mod(r, mul_mod(f, inv, pi), pi);
+        }
+        vec![r, pe - r]
+    };
+    roots.sort_unstable();
+    roots.dedup();
+    roots
+}
+
+/// How `n mod p^k` splits as `p^(2j) * u` with `u` prime to `p`: `Some(j)`,
+/// or `None` for an odd power of `p`, which has no roots. Not called for
+/// `n ≡ 0`.
+fn split_square_part(n: u64, p: u64) -> Option<u32> {
+    let mut u = n;
+    let mut twice_j = 0;
+    while u.is_multiple_of(p) {
+        u /= p;
+        twice_j += 1;
+    }
+    (twice_j % 2 == 0).then_some(twice_j / 2)
+}
+
+/// The number of [`unit_roots`] of `u` mod `p^e`, for `e >= 1`.
+fn unit_root_count(u: u64, p: u64, e: u32) -> u64 {
+    if p == 2 {
+        match e {
+            1 => 1,
+            2 if u % 4 == 1 => 2,
+            2 => 0,
+            _ if u % 8 == 1 => 4,
+            _ => 0,
+        }
+    } else if is_residue_euler(u % p, p) {
+        2
+    } else {
+        0
+    }
+}
+
+/// The number of square roots of `n` mod `p^k`, as [`sqrt_mod_prime_power`]
+/// would list them.
+fn sqrt_count_prime_power(n: u64, p: u64, k: u32) -> u64 {
+    let n = n % p.pow(k);
+    if n == 0 {
+        return p.pow(k / 2);
+    }
+    let Some(j) = split_square_part(n, p) else {
+        return 0;
+    };
+    let u = n / p.pow(2 * j);
+    unit_root_count(u, p, k - 2 * j) * p.pow(j)
+}
+
+/// The number of square roots of `n` mod `m`, given the factorisation of
+/// `m`: the product of the counts modulo each prime power. Cheap even when
+/// the roots themselves are far too many to list.
+pub fn sqrt_count(n: u64, factors: &[(u64, u32)]) -> u64 {
+    factors
+        .iter()
+        .map(|&(p, k)| sqrt_count_prime_power(n, p, k))
+        .product()
+}
+
+/// Most square roots [`sqrt_mod_crt`] and [`Modulus::sqrt`] will list, 8 MiB
+/// of them. A modulus with a large square factor, such as `2^62`, has
+/// billions of roots of 0.
+pub const MAX_ROOTS: u64 = 1 << 20;
+
+/// All square roots of `n` mod `p^k`, ascending.
+///
+/// Writing `n = p^(2j) * u` with `u` prime to `p`, the roots are
+/// `x = p^j * y` where `y` is a root of `u` mod `p^(k-2j)`, free mod
+/// `p^(k-j)`: each such root gives `p^j` roots mod `p^k`. An odd power of
+/// `p` has no roots, and `n ≡ 0` has every multiple of `p^ceil(k/2)`.
+///
+/// The caller bounds the number of roots with [`sqrt_count_prime_power`].
+fn sqrt_mod_prime_power(n: u64, p: u64, k: u32) -> Vec<u64> {
+    let pk = p.pow(k);
+    let n = n % pk;
+    if n == 0 {
+        let step = p.pow(k.div_ceil(2));
+        return (0..pk / step).map(|t| t * step).collect();
+    }
+    let Some(j) = split_square_part(n, p) else {
+        return vec![];
+    };
+    let u = n / p.pow(2 * j);
+    let pj = p.pow(j);
+    let step = p.pow(k - j);
+    let mut roots: Vec<u64> = unit_roots(u, p, k - 2 * j)
+        .into_iter()
+        .flat_map(|r| (0..pj).map(move |t| pj * r + t * step))
+        .collect();
+    roots.sort_unstable();
+    roots
+}
+
+/// All square roots of `n` mod `m`, ascending, given the factorisation of
+/// `m`: the roots modulo each prime power, combined by the Chinese remainder
+/// theorem. Fails without allocating when [`sqrt_count`] exceeds
+/// [`MAX_ROOTS`].
+pub fn sqrt_mod_crt(n: u64, factors: &[(u64, u32)]) -> Result<Vec<u64>, ResidueError> {
+    let count = sqrt_count(n, factors);
+    if count > MAX_ROOTS {
+        return Err(ResidueError::TooManyRoots(count));
+    }
+    let mut roots = vec![0];
+    let mut modulus = 1u64;
+    for &(p, k) in factors {
+        let pk = p.pow(k);
+        let local = sqrt_mod_prime_power(n, p, k);
+        if local.is_empty() {
+            return Ok(local);
+        }
+        // x ≡ a (mod modulus) and x ≡ b (mod pk) give
+        // x = a + modulus * ((b - a) / modulus mod pk).
+        let inv = inverse_mod(modulus % pk, pk).expect("prime powers are coprime");
+        roots = roots
+            .iter()
+            .flat_map(|&a| {
+                local.iter().map(move |&b| {
+                    let t = mul_mod(sub_mod(b, a % pk, pk), inv, pk);
+                    a + modulus * t
+                })
+            })
+            .collect();
+        modulus *= pk;
+    }
+    roots.sort_unstable();
+    Ok(roots)
+}
+
+/// Counts the residues mod prime `p` by Euler's criterion.
+pub fn count_euler(p: u64) -> u64 {
+    (0..p).filter(|&n| is_residue_euler(n, p)).count() as u64
+}
+
+/// Counts the residues mod prime `p` by Legendre symbols.
+pub fn count_jacobi(p: u64) -> u64 {
+    (0..p).filter(|&n| is_residue_jacobi(n, p)).count() as u64
+}
+
+/// Counts the residues mod `m` by factorising once and testing each `n`
+/// with [`is_residue_crt`].
+pub fn count_crt(m: u64) -> u64 {
+    let factors = factorize(m);
+    (0..m).filter(|&n| is_residue_crt(n, &factors)).count() as u64
+}
+
+/// The number of squares mod `m`, from its factorisation. The count is
+/// multiplicative over prime powers, and for `p^k` (Stangl, 1996) it is
+/// `(2^k + 8)/6` or `(2^k + 10)/6` for `p = 2` and even or odd `k`, and
+/// `(p^(k+1) + p + 2) / (2(p+1))` or `(p^(k+1) + 2p + 1) / (2(p+1))` for
+/// odd `p`.
+pub fn count_closed_form(m: u64) -> u64 {
+    factorize(m)
+        .into_iter()
+        .map(|(p, k)| {
+            let p = p as u128;
+            let count = match (p, k % 2) {
+                (2, 0) => (p.pow(k) + 8) / 6,
+                (2, _) => (p.pow(k) + 10) / 6,
+                (_, 0) => (p.pow(k + 1) + p + 2) / (2 * (p + 1)),
+                _ => (p.pow(k + 1) + 2 * p + 1) / (2 * (p + 1)),
+            };
+            count as u64
+        })
+        .product()
+}
+
+/// Writes a factorisation as `2^3 * 5`; the empty one, of 1, is `1`.
+pub fn format_factors(factors: &[(u64, u32)]) -> String {
+    if factors.is_empty() {
+        return "1".to_string();
+    }
+    let factors: Vec<String> = factors
+        .iter()
+        .map(|&(p, k)| match k {
+            1 => p.to_string(),
+            _ => format!("{}^{}", p, k),
+        })
+        .collect();
+    factors.join(" * ")
+}
+
+/// Checks every method that applies to `m` against the naive `quad_res` for
+/// each `n` in `0..m`, and that the square roots of each `n` are exactly the
+/// `x` with `x*x ≡ n`, returning the first disagreement.
+pub fn cross_check(m: u64) -> Result<(), String> {
+    let squares = sieve(m);
+    let factors = factorize(m);
+    let prime = is_prime(m);
+    let mut root_count = 0;
+    for n in 0..m {
+        let naive = quad_res(n as i64, m as i64) == 1;
+        let mut methods = vec![
+            ("sieve", squares[n as usize]),
+            ("crt", is_residue_crt(n, &factors)),
+        ];
+        if prime {
+            methods.push(("euler", is_residue_euler(n, m)));
+            methods.push(("jacobi", is_residue_jacobi(n, m)));
+        }
+        for (name, got) in methods {
+            if got != naive {
+                return Err(format!(
+                    "{} says {} is {}a residue mod {}, naive quad_res disagrees",
+                    name,
+                    n,
+                    if got { "" } else { "not " },
+                    m
+                ));
+            }
+        }
+        let roots = sqrt_mod_crt(n, &factors).map_err(|e| format!("{} mod {}: {}", n, m, e))?;
+        if roots.len() as u64 != sqrt_count(n, &factors) {
+            return Err(format!(
+                "sqrt lists {} roots of {} mod {}, sqrt_count says {}",
+                roots.len(),
+                n,
+                m,
+                sqrt_count(n, &factors)
+            ));
+        }
+        if roots.is_empty() == naive {
+            return Err(format!(
+                "sqrt finds {} roots of {} mod {}, naive quad_res disagrees",
+                roots.len(),
+                n,
+                m
+            ));
+        }
+        if let Some(r) = roots.iter().find(|&&r| r >= m || mul_mod(r, r, m) != n) {
+            return Err(format!("sqrt gives {} as a root of {} mod {}", r, n, m));
+        }
+        if roots.windows(2).any(|w| w[0] >= w[1]) {
+            return Err(format!("sqrt repeats a root of {} mod {}", n, m));
+        }
+        root_count += roots.len() as u64;
+    }
+    // Every x in 0..m is a root of exactly one n.
+    if root_count != m {
+        return Err(format!(
+            "sqrt finds {} roots over all residues mod {}, there are {}",
+            root_count, m, m
+        ));
+    }
+    let naive_count = squares.iter().filter(|&&s| s).count() as u64;
+    let closed = count_closed_form(m);
+    if closed != naive_count {
+        return Err(format!(
+            "closed form counts {} residues mod {}, there are {}",
+            closed, m, naive_count
+        ));
+    }
+    Ok(())
+}
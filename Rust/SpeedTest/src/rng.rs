@@ -0,0 +1,664 @@
+//! Random number generators for the Monte Carlo kernels.
+//!
+//! The pi benchmark's cost is mostly the generator, so the generator is
+//! selectable. Besides the `rand` crate's generators this module has ports
+//! of the ones the C and Python versions use: Python's `random.random()` is
+//! MT19937 with 53-bit output, and C's `rand()` is either glibc's additive
+//! feedback generator or the classic ANSI C LCG, depending on the libc.
+//! Each backend turns its raw output into a double the way its home
+//! language does, so with the same seed the points match exactly.
+
+use std::fmt;
+use std::str::FromStr;
+use std::sync::OnceLock;
+
+use rand::{Rng, SeedableRng};
+use rand_chacha::{ChaCha12Rng, ChaCha8Rng};
+
+/// A source of uniform doubles for the kernels.
+pub trait UnitRng {
+    /// The next sample in `[0, 1)`, or `[0, 1]` for the libc backends, which
+    /// divide by `RAND_MAX` like `monte_carlo_pi.c`.
+    fn next_f64(&mut self) -> f64;
+
+    /// 32 uniform random bits, for the integer kernels. Generators with
+    /// fewer bits per output combine several outputs.
+    fn next_u32(&mut self) -> u32;
+}
+
+/// The selectable generators.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Backend {
+    /// `rand::rng()`: thread-local ChaCha12 seeded from the OS, as in the
+    /// original `monte_carlo_pi.rs`. Ignores seeds.
+    Thread,
+    /// ChaCha12, the `rand` crate's `StdRng`.
+    ChaCha12,
+    /// ChaCha8, the fastest ChaCha variant.
+    ChaCha8,
+    /// PCG32 (XSH-RR), two outputs per double.
+    Pcg32,
+    /// Xoshiro256++.
+    Xoshiro256PlusPlus,
+    /// SplitMix64.
+    SplitMix64,
+    /// MT19937 with Python's seeding and 53-bit `random()`.
+    Mt19937,
+    /// The ANSI C example `rand()` LCG, `RAND_MAX` 32767.
+    AnsiLcg,
+    /// glibc's `rand()`, `RAND_MAX` 2^31-1.
+    Glibc,
+}
+
+impl Backend {
+    pub const ALL: [Backend; 9] = [
+        Backend::Thread,
+        Backend::ChaCha12,
+        Backend::ChaCha8,
+        Backend::Pcg32,
+        Backend::Xoshiro256PlusPlus,
+        Backend::SplitMix64,
+        Backend::Mt19937,
+        Backend::AnsiLcg,
+        Backend::Glibc,
+    ];
+
+    pub fn name(self) -> &'static str {
+        match self {
+            Backend::Thread => "thread",
+            Backend::ChaCha12 => "chacha12",
+            Backend::ChaCha8 => "chacha8",
+            Backend::Pcg32 => "pcg32",
+            Backend::Xoshiro256PlusPlus => "xoshiro256pp",
+            Backend::SplitMix64 => "splitmix64",
+            Backend::Mt19937 => "mt19937",
+            Backend::AnsiLcg => "lcg",
+            Backend::Glibc => "glibc",
+        }
+    }
+
+    /// Calls `f` with this backend seeded for stream `stream` of `seed`.
+    ///
+    /// ChaCha and PCG have native stream selectors and Xoshiro is jumped
+    /// 2^128 steps per stream, which costs one jump per set bit of `stream`.
+    /// The others have no stream support, so their seed for stream `k > 0`
+    /// is SplitMix64-mixed from `seed` and `k`.
+    /// Stream 0 always uses `seed` unchanged, which keeps single-threaded
+    /// runs comparable with the C and Python programs.
+    pub fn with_rng<T>(self, seed: u64, stream: u64, f: impl RngFn<T>) -> T {
+        let mixed = if stream == 0 {
+            seed
+        } else {
+            SplitMix64::new(seed ^ stream.wrapping_mul(0x9e37_79b9_7f4a_7c15)).next_u64()
+        };
+        match self {
+            Backend::Thread => f.call(&mut RandRng(rand::rng())),
+            Backend::ChaCha12 => {
+                let mut rng = ChaCha12Rng::seed_from_u64(seed);
+                rng.set_stream(stream);
+                f.call(&mut RandRng(rng))
+            }
+            Backend::ChaCha8 => {
+                let mut rng = ChaCha8Rng::seed_from_u64(seed);
+                rng.set_stream(stream);
+                f.call(&mut RandRng(rng))
+            }
+            Backend::Pcg32 => f.call(&mut Pcg32::new(seed, stream)),
+            Backend::Xoshiro256PlusPlus => {
+                let mut rng = Xoshiro256PlusPlus::new(seed);
+                rng.jump_streams(stream);
+                f.call(&mut rng)
+            }
+            Backend::SplitMix64 => f.call(&mut SplitMix64::new(mixed)),
+            Backend::Mt19937 => f.call(&mut Mt19937::new(mixed)),
+            Backend::AnsiLcg => f.call(&mut AnsiLcg::new(mixed as u32)),
+            Backend::Glibc => f.call(&mut GlibcRand::new(mixed as u32)),
+        }
+    }
+}
+
+/// A kernel generic over the generator. A trait rather than a closure so the
+/// kernel is monomorphised per backend and the hot loop has no dispatch.
+pub trait RngFn<T> {
+    fn call<R: UnitRng>(self, rng: &mut R) -> T;
+}
+
+impl fmt::Display for Backend {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.name())
+    }
+}
+
+impl FromStr for Backend {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Backend::ALL
+            .into_iter()
+            .find(|b| b.name() == s)
+            .ok_or_else(|| {
+                let names: Vec<&str> = Backend::ALL.iter().map(|b| b.name()).collect();
+                format!("unknown RNG `{}` (one of {})", s, names.join(", "))
+            })
+    }
+}
+
+/// Adapts a `rand` generator, using `rand`'s own 53-bit conversion.
+pub struct RandRng<R>(pub R);
+
+impl<R: Rng> UnitRng for RandRng<R> {
+    #[inline]
+    fn next_f64(&mut self) -> f64 {
+        self.0.random::<f64>()
+    }
+
+    #[inline]
+    fn next_u32(&mut self) -> u32 {
+        self.0.next_u32()
+    }
+}
+
+/// 53 high bits of `x` as a double in `[0, 1)`.
+#[inline]
+fn u64_to_unit(x: u64) -> f64 {
+    (x >> 11) as f64 * (1.0 / (1u64 << 53) as f64)
+}
+
+/// Python's `genrand_res53`: 27 + 26 bits from two 32-bit outputs.
+#[inline]
+fn res53(a: u32, b: u32) -> f64 {
+    let (a, b) = ((a >> 5) as f64, (b >> 6) as f64);
+    (a * 67108864.0 + b) * (1.0 / 9007199254740992.0)
+}
+
+/// SplitMix64 (Steele, Lea and Flood).
+pub struct SplitMix64 {
+    state: u64,
+}
+
+impl SplitMix64 {
+    pub fn new(seed: u64) -> Self {
+        SplitMix64 { state: seed }
+    }
+
+    #[inline]
+    pub fn next_u64(&mut self) -> u64 {
+        self.state = self.state.wrapping_add(0x9e37_79b9_7f4a_7c15);
+        let mut z = self.state;
+        z = (z ^ (z >> 30)).wrapping_mul(0xbf58_476d_1ce4_e5b9);
+        z = (z ^ (z >> 27)).wrapping_mul(0x94d0_49bb_1331_11eb);
+        z ^ (z >> 31)
+    }
+}
+
+impl UnitRng for SplitMix64 {
+    #[inline]
+    fn next_f64(&mut self) -> f64 {
+        u64_to_unit(self.next_u64())
+    }
+
+    #[inline]
+    fn next_u32(&mut self) -> u32 {
+        (self.next_u64() >> 32) as u32
+    }
+}
+
+/// Xoshiro256++ (Blackman and Vigna), seeded through SplitMix64 as the
+/// authors recommend.
+pub struct Xoshiro256PlusPlus {
+    s: [u64; 4],
+}
+
+impl Xoshiro256PlusPlus {
+    pub fn new(seed: u64) -> Self {
+        let mut sm = SplitMix64::new(seed);
+        Xoshiro256PlusPlus {
+            s: [sm.next_u64(), sm.next_u64(), sm.next_u64(), sm.next_u64()],
+        }
+    }
+
+    #[inline]
+    pub fn next_u64(&mut self) -> u64 {
+        let s = &mut self.s;
+        let result = s[0].wrapping_add(s[3]).rotate_left(23).wrapping_add(s[0]);
+        let t = s[1] << 17;
+        s[2] ^= s[0];
+        s[3] ^= s[1];
+        s[1] ^= s[2];
+        s[0] ^= s[3];
+        s[2] ^= t;
+        s[3] = s[3].rotate_left(45);
+        result
+    }
+
+    /// Advances 2^128 steps, giving a non-overlapping subsequence.
+    pub fn jump(&mut self) {
+        const JUMP: [u64; 4] = [
+            0x180e_c6d3_3cfd_0aba,
+            0xd5a6_1266_f0c9_392c,
+            0xa958_2618_e03f_c9aa,
+            0x39ab_dc45_29b1_661c,
+        ];
+        let mut acc = [0u64; 4];
+        for word in JUMP {
+            for bit in 0..64 {
+                if word & (1u64 << bit) != 0 {
+                    for (a, s) in acc.iter_mut().zip(&self.s) {
+                        *a ^= s;
+                    }
+                }
+                self.next_u64();
+            }
+        }
+        self.s = acc;
+    }
+
+    /// Advances `streams * 2^128` steps, as `streams` calls to
+    /// [`Xoshiro256PlusPlus::jump`] would, but with one matrix product per
+    /// set bit of `streams`. The matrices are built on first use, which in
+    /// a benchmark is the warmup run.
+    pub fn jump_streams(&mut self, streams: u64) {
+        if streams == 0 {
+            return;
+        }
+        static JUMPS: OnceLock<Vec<JumpMatrix>> = OnceLock::new();
+        let jumps = JUMPS.get_or_init(|| {
+            let mut jumps = vec![JumpMatrix::jump()];
+            for _ in 1..64 {
+                let squared = jumps[jumps.len() - 1].squared();
+                jumps.push(squared);
+            }
+            jumps
+        });
+        for (bit, jump) in jumps.iter().enumerate() {
+            if streams & (1u64 << bit) != 0 {
+                self.s = jump.apply(&self.s);
+            }
+        }
+    }
+}
+
+/// A linear map of Xoshiro256 states, stored as the images of the 256 unit
+/// states. The generator's step is linear over GF(2), so any number of
+/// steps is such a map.
+struct JumpMatrix(Vec<[u64; 4]>);
+
+impl JumpMatrix {
+    /// The map [`Xoshiro256PlusPlus::jump`] applies.
+    fn jump() -> Self {
+        JumpMatrix(
+            (0..256)
+                .map(|bit| {
+                    let mut s = [0u64; 4];
+                    s[bit / 64] = 1 << (bit % 64);
+                    let mut rng = Xoshiro256PlusPlus { s };
+                    rng.jump();
+                    rng.s
+                })
+                .collect(),
+        )
+    }
+
+    fn apply(&self, s: &[u64; 4]) -> [u64; 4] {
+        let mut out = [0u64; 4];
+        for (bit, image) in self.0.iter().enumerate() {
+            if s[bit / 64] & (1 << (bit % 64)) != 0 {
+                for (o, x) in out.iter_mut().zip(image) {
+                    *o ^= x;
+                }
+            }
+        }
+        out
+    }
+
+    /// The map applied twice.
+    fn squared(&self) -> Self {
+        JumpMatrix(self.0.iter().map(|image| self.apply(image)).collect())
+    }
+}
+
+impl UnitRng for Xoshiro256PlusPlus {
+    #[inline]
+    fn next_f64(&mut self) -> f64 {
+        u64_to_unit(self.next_u64())
+    }
+
+    #[inline]
+    fn next_u32(&mut self) -> u32 {
+        (self.next_u64() >> 32) as u32
+    }
+}
+
+/// PCG32 XSH-RR (O'Neill), `pcg32_srandom_r(seed, stream)`.
+pub struct Pcg32 {
+    state: u64,
+    inc: u64,
+}
+
+impl Pcg32 {
+    const MULTIPLIER: u64 = 6364136223846793005;
+
+    pub fn new(seed: u64, stream: u64) -> Self {
+        let mut rng = Pcg32 {
+            state: 0,
+            inc: (stream << 1) | 1,
+        };
+        rng.next_u32();
+        rng.state = rng.state.wrapping_add(seed);
+        rng.next_u32();
+        rng
+    }
+
+    #[inline]
+    pub fn next_u32(&mut self) -> u32 {
+        let old = self.state;
+        self.state = old.wrapping_mul(Self::MULTIPLIER).wrapping_add(self.inc);
+        let xorshifted = (((old >> 18) ^ old) >> 27) as u32;
+        let rot = (old >> 59) as u32;
+        xorshifted.rotate_right(rot)
+    }
+}
+
+impl UnitRng for Pcg32 {
+    #[inline]
+    fn next_f64(&mut self) -> f64 {
+        let hi = self.next_u32() as u64;
+        let lo = self.next_u32() as u64;
+        u64_to_unit((hi << 32) | lo)
+    }
+
+    #[inline]
+    fn next_u32(&mut self) -> u32 {
+        Pcg32::next_u32(self)
+    }
+}
+
+/// MT19937, seeded like CPython's `random.seed(int)` so a seed gives the same
+/// stream as `monte_carlo_pi.py` with `random.seed(seed)`.
+pub struct Mt19937 {
+    mt: [u32; 624],
+    index: usize,
+}
+
+impl Mt19937 {
+    pub fn new(seed: u64) -> Self {
+        // CPython splits abs(seed) into 32-bit words, least significant
+        // first, and always passes at least one word.
+        let key: Vec<u32> = if seed >> 32 == 0 {
+            vec![seed as u32]
+        } else {
+            vec![seed as u32, (seed >> 32) as u32]
+        };
+        Self::by_array(&key)
+    }
+
+    fn by_seed(seed: u32) -> Self {
+        let mut mt = [0u32; 624];
+        mt[0] = seed;
+        for i in 1..624 {
+            mt[i] = 1812433253u32
+                .wrapping_mul(mt[i - 1] ^ (mt[i - 1] >> 30))
+                .wrapping_add(i as u32);
+        }
+        Mt19937 { mt, index: 624 }
+    }
+
+    /// The reference `init_by_array`.
+    fn by_array(key: &[u32]) -> Self {
+        let mut rng = Self::by_seed(19650218);
+        let mt = &mut rng.mt;
+        let (mut i, mut j) = (1usize, 0usize);
+        for _ in 0..624.max(key.len()) {
+            mt[i] = (mt[i] ^ (mt[i - 1] ^ (mt[i - 1] >> 30)).wrapping_mul(1664525))
+                .wrapping_add(key[j])
+                .wrapping_add(j as u32);
+            i += 1;
+            j += 1;
+            if i >= 624 {
+                mt[0] = mt[623];
+                i = 1;
+            }
+            if j >= key.len() {
+                j = 0;
+            }
+        }
+        for _ in 0..623 {
+            mt[i] = (mt[i] ^ (mt[i - 1] ^ (mt[i - 1] >> 30)).wrapping_mul(1566083941))
+                .wrapping_sub(i as u32);
+            i += 1;
+            if i >= 624 {
+                mt[0] = mt[623];
+                i = 1;
+            }
+        }
+        mt[0] = 0x8000_0000;
+        rng
+    }
+
+    fn twist(&mut self) {
+        const N: usize = 624;
+        const M: usize = 397;
+        #[inline(always)]
+        fn mix(upper: u32, lower: u32, far: u32) -> u32 {
+            let y = (upper & 0x8000_0000) | (lower & 0x7fff_ffff);
+            far ^ (y >> 1) ^ (if y & 1 != 0 { 0x9908_b0df } else { 0 })
+        }
+        let mt = &mut self.mt;
+        for i in 0..N - M {
+            mt[i] = mix(mt[i], mt[i + 1], mt[i + M]);
+        }
+        for i in N - M..N - 1 {
+            mt[i] = mix(mt[i], mt[i + 1], mt[i + M - N]);
+        }
+        mt[N - 1] = mix(mt[N - 1], mt[0], mt[M - 1]);
+        self.index = 0;
+    }
+
+    #[inline]
+    pub fn next_u32(&mut self) -> u32 {
+        if self.index >= 624 {
+            self.twist();
+        }
+        let mut y = self.mt[self.index];
+        self.index += 1;
+        y ^= y >> 11;
+        y ^= (y << 7) & 0x9d2c_5680;
+        y ^= (y << 15) & 0xefc6_0000;
+        y ^ (y >> 18)
+    }
+}
+
+impl UnitRng for Mt19937 {
+    #[inline]
+    fn next_f64(&mut self) -> f64 {
+        let a = self.next_u32();
+        let b = self.next_u32();
+        res53(a, b)
+    }
+
+    #[inline]
+    fn next_u32(&mut self) -> u32 {
+        Mt19937::next_u32(self)
+    }
+}
+
+/// The example `rand()` from the C standard: `next = next * 1103515245 +
+/// 12345`, returning bits 16..30. Used by several small libcs.
+pub struct AnsiLcg {
+    next: u32,
+}
+
+impl AnsiLcg {
+    pub const RAND_MAX: u32 = 32767;
+
+    pub fn new(seed: u32) -> Self {
+        AnsiLcg { next: seed }
+    }
+
+    #[inline]
+    pub fn rand(&mut self) -> u32 {
+        self.next = self.next.wrapping_mul(1103515245).wrapping_add(12345);
+        (self.next / 65536) % 32768
+    }
+}
+
+impl UnitRng for AnsiLcg {
+    #[inline]
+    fn next_f64(&mut self) -> f64 {
+        self.rand() as f64 / Self::RAND_MAX as f64
+    }
+
+    /// 15 + 15 + 2 bits from three calls.
+    #[inline]
+    fn next_u32(&mut self) -> u32 {
+        (self.rand() << 17) | (self.rand() << 2) | (self.rand() & 3)
+    }
+}
+
+/// glibc's `rand()`/`random()`: the TYPE_3 additive feedback generator
+/// `r[i] = r[i-3] + r[i-31]`, seeded with the Park-Miller LCG and with the
+/// first 310 outputs discarded, exactly as `srand(seed)` does.
+pub struct GlibcRand {
+    r: [u32; 34],
+    i: usize,
+}
+
+impl GlibcRand {
+    pub const RAND_MAX: u32 = 2147483647;
+
+    pub fn new(seed: u32) -> Self {
+        let mut r = [0u32; 34];
+        r[0] = if seed == 0 { 1 } else { seed };
+        for i in 1..31 {
+            // 16807 * r[i-1] % (2^31 - 1) without overflow (Schrage).
+            let prev = r[i - 1] as i32 as i64;
+            let hi = prev / 127773;
+            let lo = prev % 127773;
+            let mut word = 16807 * lo - 2836 * hi;
+            if word < 0 {
+                word += 2147483647;
+            }
+            r[i] = word as u32;
+        }
+        for i in 31..34 {
+            r[i] = r[i - 31];
+        }
+        let mut rng = GlibcRand { r, i: 34 };
+        for _ in 34..344 {
+            rng.step();
+        }
+        rng
+    }
+
+    #[inline]
+    fn step(&mut self) -> u32 {
+        let i = self.i % 34;
+        let v = self.r[(i + 3) % 34].wrapping_add(self.r[(i + 31) % 34]);
+        self.r[i] = v;
+        self.i = i + 1;
+        v
+    }
+
+    #[inline]
+    pub fn rand(&mut self) -> u32 {
+        self.step() >> 1
+    }
+}
+
+impl UnitRng for GlibcRand {
+    #[inline]
+    fn next_f64(&mut self) -> f64 {
+        self.rand() as f64 / Self::RAND_MAX as f64
+    }
+
+    /// 31 + 1 bits from two calls.
+    #[inline]
+    fn next_u32(&mut self) -> u32 {
+        (self.rand() << 1) | (self.rand() & 1)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn mt19937_matches_cpython_random() {
+        // `random.seed(s); [random.random() for _ in range(3)]`, with a seed
+        // of one and of two 32-bit words.
+        let cases: [(u64, [f64; 3]); 2] = [
+            (
+                42,
+                [
+                    0.6394267984578837,
+                    0.025010755222666936,
+                    0.27502931836911926,
+                ],
+            ),
+            (
+                (1 << 40) + 5,
+                [0.5043802970418443, 0.2686044399723282, 0.9257865475671585],
+            ),
+        ];
+        for (seed, want) in cases {
+            let mut rng = Mt19937::new(seed);
+            let got: Vec<f64> = (0..3).map(|_| rng.next_f64()).collect();
+            assert_eq!(got, want, "seed {}", seed);
+        }
+    }
+
+    #[test]
+    fn glibc_rand_matches_srand_1() {
+        // glibc's `rand()` after `srand(1)`.
+        let mut rng = GlibcRand::new(1);
+        let got: Vec<u32> = (0..5).map(|_| rng.rand()).collect();
+        assert_eq!(
+            got,
+            [1804289383, 846930886, 1681692777, 1714636915, 1957747793]
+        );
+    }
+
+    #[test]
+    fn xoshiro_matches_the_reference_implementation() {
+        // From the authors' xoshiro256plusplus.c with the state {1, 2, 3, 4}.
+        let mut rng = Xoshiro256PlusPlus { s: [1, 2, 3, 4] };
+        let got: Vec<u64> = (0..3).map(|_| rng.next_u64()).collect();
+        assert_eq!(
+            got,
+            [
+                0x0000_0000_0280_0001,
+                0x0000_0000_0380_0067,
+                0x000c_c000_0380_0067
+            ]
+        );
+
+        let mut rng = Xoshiro256PlusPlus { s: [1, 2, 3, 4] };
+        rng.jump();
+        assert_eq!(
+            rng.s,
+            [
+                0x8c7a_1539_56b5_f3d1,
+                0x701f_1a71_3401_d85e,
+                0x6527_f66a_6546_9085,
+                0x8386_b786_c440_8050
+            ]
+        );
+
+        let mut rng = Xoshiro256PlusPlus { s: [1, 2, 3, 4] };
+        rng.jump_streams(5);
+        assert_eq!(rng.next_u64(), 0x2ca2_527b_8c44_64d8);
+    }
+
+    #[test]
+    fn jump_streams_is_repeated_jumps() {
+        for streams in [0, 1, 2, 3, 6, 13] {
+            let mut jumped = Xoshiro256PlusPlus::new(7);
+            for _ in 0..streams {
+                jumped.jump();
+            }
+            let mut rng = Xoshiro256PlusPlus::new(7);
+            rng.jump_streams(streams);
+            assert_eq!(rng.s, jumped.s, "{} streams", streams);
+        }
+    }
+}
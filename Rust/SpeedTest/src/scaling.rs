@@ -0,0 +1,92 @@
+//! Strong and weak scaling of multithreaded benchmarks.
+//!
+//! Works with any benchmark that has `threads` and `iterations` parameters.
+//! Strong scaling keeps the total work fixed while adding threads; the ideal
+//! is a run time that falls as 1/threads. Weak scaling keeps the work per
+//! thread fixed; the ideal is a flat run time.
+
+use crate::{bench, Benchmark, ParamError, RunConfig};
+
+/// One thread count in a scaling run.
+#[derive(Debug, Clone, Copy)]
+pub struct ScalingPoint {
+    pub threads: u64,
+    pub iterations: u64,
+    pub median_ns: f64,
+    /// Single-thread time over this time.
+    pub speedup: f64,
+    /// Speedup relative to the ideal, 1.0 being perfect.
+    pub efficiency: f64,
+}
+
+#[derive(Debug, Clone)]
+pub struct Scaling {
+    pub benchmark: &'static str,
+    pub strong: Vec<ScalingPoint>,
+    pub weak: Vec<ScalingPoint>,
+}
+
+/// Powers of two up to `max`, plus `max` itself.
+pub fn thread_counts(max: u64) -> Vec<u64> {
+    let mut counts: Vec<u64> = std::iter::successors(Some(1u64), |&t| Some(t * 2))
+        .take_while(|&t| t < max)
+        .collect();
+    counts.push(max.max(1));
+    counts
+}
+
+/// Measures `bench` at each of [`thread_counts`]`(max_threads)`. The strong
+/// series uses the benchmark's configured `iterations`; the weak series gives
+/// each thread `iterations / max_threads`, so its largest run does the same
+/// total work as the strong series.
+pub fn scaling(
+    bench: &dyn Benchmark,
+    run: &RunConfig,
+    max_threads: u64,
+) -> Result<Scaling, ParamError> {
+    let iterations: u64 = bench
+        .params()
+        .into_iter()
+        .find(|(k, _)| *k == "iterations")
+        .and_then(|(_, v)| v.parse().ok())
+        .ok_or_else(|| ParamError::Unknown("iterations".to_string()))?;
+    let counts = thread_counts(max_threads);
+    let per_thread = (iterations / max_threads).max(1);
+
+    let strong = series(bench, run, &counts, |_| iterations, |t| t as f64)?;
+    let weak = series(bench, run, &counts, |t| per_thread * t, |_| 1.0)?;
+    Ok(Scaling {
+        benchmark: bench.name(),
+        strong,
+        weak,
+    })
+}
+
+/// Runs one series. `ideal_speedup` is what a perfectly scaling kernel would
+/// achieve at a given thread count.
+fn series(
+    bench: &dyn Benchmark,
+    run: &RunConfig,
+    counts: &[u64],
+    iterations_for: impl Fn(u64) -> u64,
+    ideal_speedup: impl Fn(u64) -> f64,
+) -> Result<Vec<ScalingPoint>, ParamError> {
+    let mut points: Vec<ScalingPoint> = Vec::with_capacity(counts.len());
+    for &threads in counts {
+        let iterations = iterations_for(threads);
+        let mut b = bench.clone_box();
+        b.set_param("threads", &threads.to_string())?;
+        b.set_param("iterations", &iterations.to_string())?;
+        let median_ns = bench::measure(b.as_ref(), run).summary.median;
+        let base = points.first().map_or(median_ns, |p| p.median_ns);
+        let speedup = base / median_ns;
+        points.push(ScalingPoint {
+            threads,
+            iterations,
+            median_ns,
+            speedup,
+            efficiency: speedup / ideal_speedup(threads),
+        });
+    }
+    Ok(points)
+}
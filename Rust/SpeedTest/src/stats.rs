@@ -0,0 +1,235 @@
+//! Summary statistics over repeated timings.
+
+use std::time::Duration;
+
+use serde::{Deserialize, Serialize};
+
+/// Modified z-score above which a sample counts as an outlier
+/// (Iglewicz and Hoaglin).
+const OUTLIER_Z: f64 = 3.5;
+
+/// Statistics over a set of timings, all in nanoseconds.
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct Summary {
+    pub runs: usize,
+    pub min: f64,
+    pub max: f64,
+    pub median: f64,
+    pub mean: f64,
+    pub stddev: f64,
+    /// Half-width of the 95% confidence interval around the mean.
+    pub ci95: f64,
+    /// Median absolute deviation from the median.
+    pub mad: f64,
+    /// Samples whose MAD-based modified z-score exceeds 3.5.
+    pub outliers: usize,
+}
+
+impl Summary {
+    pub fn from_durations(samples: &[Duration]) -> Summary {
+        let ns: Vec<f64> = samples.iter().map(|d| d.as_nanos() as f64).collect();
+        Summary::from_nanos(&ns)
+    }
+
+    pub fn from_nanos(samples: &[f64]) -> Summary {
+        assert!(!samples.is_empty(), "cannot summarise zero samples");
+        let mut sorted = samples.to_vec();
+        sorted.sort_by(f64::total_cmp);
+        let runs = sorted.len();
+        let mean = sorted.iter().sum::<f64>() / runs as f64;
+        let stddev = if runs > 1 {
+            let var = sorted.iter().map(|x| (x - mean).powi(2)).sum::<f64>() / (runs - 1) as f64;
+            var.sqrt()
+        } else {
+            0.0
+        };
+        let ci95 = if runs > 1 {
+            t_critical_95(runs - 1) * stddev / (runs as f64).sqrt()
+        } else {
+            0.0
+        };
+        let median = median_of_sorted(&sorted);
+        let mut deviations: Vec<f64> = sorted.iter().map(|x| (x - median).abs()).collect();
+        deviations.sort_by(f64::total_cmp);
+        let mad = median_of_sorted(&deviations);
+        let outliers = if mad > 0.0 {
+            sorted
+                .iter()
+                .filter(|x| (0.6745 * (*x - median) / mad).abs() > OUTLIER_Z)
+                .count()
+        } else {
+            0
+        };
+        Summary {
+            runs,
+            min: sorted[0],
+            max: sorted[runs - 1],
+            median,
+            mean,
+            stddev,
+            ci95,
+            mad,
+            outliers,
+        }
+    }
+}
+
+pub fn median_of_sorted(sorted: &[f64]) -> f64 {
+    let n = sorted.len();
+    if n % 2 == 1 {
+        sorted[n / 2]
+    } else {
+        (sorted[n / 2 - 1] + sorted[n / 2]) / 2.0
+    }
+}
+
+/// Two-sided 95% critical value of Student's t for `df` degrees of freedom.
+pub fn t_critical_95(df: usize) -> f64 {
+    const TABLE: [f64; 30] = [
+        12.706, 4.303, 3.182, 2.776, 2.571, 2.447, 2.365, 2.306, 2.262, 2.228, 2.201, 2.179, 2.160,
+        2.145, 2.131, 2.120, 2.110, 2.101, 2.093, 2.086, 2.080, 2.074, 2.069, 2.064, 2.060, 2.056,
+        2.052, 2.048, 2.045, 2.042,
+    ];
+    match df {
+        0 => f64::NAN,
+        1..=30 => TABLE[df - 1],
+        31..=60 => 2.000,
+        61..=120 => 1.980,
+        _ => 1.960,
+    }
+}
+
+/// Formats a nanosecond figure with a unit that keeps it readable.
+pub fn format_ns(ns: f64) -> String {
+    if ns >= 1e9 {
+        format!("{:.3} s", ns / 1e9)
+    } else if ns >= 1e6 {
+        format!("{:.3} ms", ns / 1e6)
+    } else if ns >= 1e3 {
+        format!("{:.3} us", ns / 1e3)
+    } else {
+        format!("{:.0} ns", ns)
+    }
+}
+
+/// Result of a two-sided Mann-Whitney U test.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct MannWhitney {
+    /// U statistic for the first sample.
+    pub u: f64,
+    /// Two-sided p-value from the tie-corrected normal approximation.
+    pub p_value: f64,
+}
+
+/// Tests whether two sets of timings come from the same distribution without
+/// assuming they are normal. Uses the normal approximation with tie and
+/// continuity corrections, which is adequate from about five samples each.
+pub fn mann_whitney_u(a: &[f64], b: &[f64]) -> MannWhitney {
+    let (n1, n2) = (a.len() as f64, b.len() as f64);
+    let mut all: Vec<(f64, bool)> = a
+        .iter()
+        .map(|&x| (x, true))
+        .chain(b.iter().map(|&x| (x, false)))
+        .collect();
+    all.sort_by(|x, y| x.0.total_cmp(&y.0));
+
+    // Average ranks over ties, and collect the tie correction as we go.
+    let mut rank_sum_a = 0.0;
+    let mut tie_term = 0.0;
+    let mut i = 0;
+    while i < all.len() {
+        let mut j = i;
+        while j + 1 < all.len() && all[j + 1].0 == all[i].0 {
+            j += 1;
+        }
+        let avg_rank = (i + j) as f64 / 2.0 + 1.0;
+        let t = (j - i + 1) as f64;
+        tie_term += t * t * t - t;
+        rank_sum_a += all[i..=j].iter().filter(|x| x.1).count() as f64 * avg_rank;
+        i = j + 1;
+    }
+
+    let u = rank_sum_a - n1 * (n1 + 1.0) / 2.0;
+    let n = n1 + n2;
+    let mean = n1 * n2 / 2.0;
+    let var = n1 * n2 / 12.0 * ((n + 1.0) - tie_term / (n * (n - 1.0)));
+    let p_value = if var > 0.0 {
+        let z = ((u - mean).abs() - 0.5).max(0.0) / var.sqrt();
+        erfc(z / std::f64::consts::SQRT_2).min(1.0)
+    } else {
+        1.0
+    };
+    MannWhitney { u, p_value }
+}
+
+/// Complementary error function, accurate to about 1.2e-7 (Numerical
+/// Recipes' Chebyshev fit).
+pub fn erfc(x: f64) -> f64 {
+    let z = x.abs();
+    let t = 1.0 / (1.0 + 0.5 * z);
+    let r = t
+        * (-z * z - 1.26551223
+            + t * (1.00002368
+                + t * (0.37409196
+                    + t * (0.09678418
+                        + t * (-0.18628806
+                            + t * (0.27886807
+                                + t * (-1.13520398
+                                    + t * (1.48851587 + t * (-0.82215223 + t * 0.17087277)))))))))
+            .exp();
+    if x >= 0.0 {
+        r
+    } else {
+        2.0 - r
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn mann_whitney_with_ties() {
+        // Pooled and sorted: 1a 2a 2a 2b 3a 3b 4b 5b 5b, so the ranks of `a`
+        // are 1, 3, 3 and 5.5, and U = 12.5 - 4*5/2 = 2.5. The ties (3, 2
+        // and 2 values) give sum(t^3 - t) = 36, so the variance is
+        // 4*5/12 * (10 - 36/72) = 15.83 and z = (|2.5 - 10| - 0.5) / 3.979.
+        let r = mann_whitney_u(&[1.0, 2.0, 2.0, 3.0], &[2.0, 3.0, 4.0, 5.0, 5.0]);
+        assert_eq!(r.u, 2.5);
+        assert!((r.p_value - 0.078_545_85).abs() < 1e-6, "{}", r.p_value);
+        // Identical samples are all ties and give no evidence at all.
+        assert_eq!(mann_whitney_u(&[1.0; 5], &[1.0; 5]).p_value, 1.0);
+    }
+
+    #[test]
+    fn erfc_known_values() {
+        for (x, want) in [
+            (0.0, 1.0),
+            (0.5, 0.479_500_122),
+            (1.0, 0.157_299_207),
+            (2.0, 0.004_677_735),
+            (-1.0, 1.842_700_793),
+        ] {
+            assert!((erfc(x) - want).abs() < 2e-7, "erfc({}) = {}", x, erfc(x));
+        }
+    }
+
+    #[test]
+    fn t_critical_values() {
+        assert!(t_critical_95(0).is_nan());
+        assert_eq!(t_critical_95(1), 12.706);
+        assert_eq!(t_critical_95(10), 2.228);
+        assert_eq!(t_critical_95(usize::MAX), 1.960);
+    }
+
+    #[test]
+    fn mad_flags_one_planted_outlier() {
+        // Median 12.5; absolute deviations 0.5, 0.5, 1.5, 1.5, 2.5 and 87.5,
+        // so MAD = 1.5 and only 100 has a modified z-score above 3.5.
+        let s = Summary::from_nanos(&[12.0, 10.0, 100.0, 13.0, 11.0, 14.0]);
+        assert_eq!(s.median, 12.5);
+        assert_eq!(s.mad, 1.5);
+        assert_eq!(s.outliers, 1);
+        assert_eq!((s.min, s.max), (10.0, 100.0));
+    }
+}
@@ -0,0 +1,129 @@
+//! Scaling sweeps: timing a benchmark over a geometric series of sizes and
+//! fitting how its time grows.
+
+use crate::stats::Summary;
+use crate::{bench, Benchmark, ParamError, RunConfig};
+
+/// Which sizes a sweep visits.
+#[derive(Debug, Clone, Copy)]
+pub struct SweepConfig {
+    /// Number of sizes.
+    pub steps: usize,
+    /// Ratio between consecutive sizes.
+    pub ratio: f64,
+}
+
+impl Default for SweepConfig {
+    fn default() -> Self {
+        SweepConfig {
+            steps: 6,
+            ratio: 2.0,
+        }
+    }
+}
+
+/// One size in a sweep.
+#[derive(Debug, Clone)]
+pub struct SweepPoint {
+    pub size: u64,
+    pub work: f64,
+    pub summary: Summary,
+    /// Set when the run at this size computed a wrong answer.
+    pub verify_error: Option<String>,
+}
+
+impl SweepPoint {
+    /// Median nanoseconds per unit of work.
+    pub fn ns_per_work(&self) -> f64 {
+        self.summary.median / self.work
+    }
+}
+
+/// A finished sweep with its fitted power law `time ~ size^exponent`.
+#[derive(Debug, Clone)]
+pub struct Sweep {
+    pub benchmark: &'static str,
+    pub param: &'static str,
+    pub points: Vec<SweepPoint>,
+    /// Fitted exponent of the median time in the size.
+    pub exponent: f64,
+    /// Goodness of the log-log fit.
+    pub r_squared: f64,
+    /// Exponent of the benchmark's own work model in the size, i.e. the
+    /// complexity the time should show if nothing else interferes.
+    pub work_exponent: f64,
+}
+
+/// Times `bench` at sizes ending at its current value of
+/// [`Benchmark::size_param`], each `ratio` times the one before.
+pub fn sweep(
+    bench: &dyn Benchmark,
+    run: &RunConfig,
+    config: &SweepConfig,
+) -> Result<Sweep, ParamError> {
+    let param = bench.size_param();
+    let current = bench
+        .params()
+        .into_iter()
+        .find(|(k, _)| *k == param)
+        .and_then(|(_, v)| v.parse::<f64>().ok())
+        .ok_or_else(|| ParamError::Unknown(param.to_string()))?;
+
+    let mut sizes: Vec<u64> = (0..config.steps)
+        .map(|k| {
+            let shrink = config.ratio.powi((config.steps - 1 - k) as i32);
+            ((current / shrink).round() as u64).max(2)
+        })
+        .collect();
+    sizes.dedup();
+
+    let mut points = Vec::with_capacity(sizes.len());
+    for size in sizes {
+        let mut b = bench.clone_box();
+        b.set_param(param, &size.to_string())?;
+        let m = bench::measure(b.as_ref(), run);
+        points.push(SweepPoint {
+            size,
+            work: b.work(),
+            summary: m.summary,
+            verify_error: b.expected().check(m.value).err(),
+        });
+    }
+
+    let xs: Vec<f64> = points.iter().map(|p| p.size as f64).collect();
+    let times: Vec<f64> = points.iter().map(|p| p.summary.median).collect();
+    let works: Vec<f64> = points.iter().map(|p| p.work).collect();
+    let (exponent, r_squared) = fit_power_law(&xs, &times);
+    let (work_exponent, _) = fit_power_law(&xs, &works);
+    Ok(Sweep {
+        benchmark: bench.name(),
+        param,
+        points,
+        exponent,
+        r_squared,
+        work_exponent,
+    })
+}
+
+/// Least-squares fit of `ln y = a + b ln x`. Returns `(b, r^2)`, or NaNs when
+/// there are fewer than two distinct sizes.
+pub fn fit_power_law(xs: &[f64], ys: &[f64]) -> (f64, f64) {
+    let lx: Vec<f64> = xs.iter().map(|x| x.ln()).collect();
+    let ly: Vec<f64> = ys.iter().map(|y| y.ln()).collect();
+    let n = lx.len() as f64;
+    let mx = lx.iter().sum::<f64>() / n;
+    let my = ly.iter().sum::<f64>() / n;
+    let sxx: f64 = lx.iter().map(|x| (x - mx).powi(2)).sum();
+    let sxy: f64 = lx.iter().zip(&ly).map(|(x, y)| (x - mx) * (y - my)).sum();
+    let syy: f64 = ly.iter().map(|y| (y - my).powi(2)).sum();
+    if lx.len() < 2 || sxx == 0.0 {
+        return (f64::NAN, f64::NAN);
+    }
+    let slope = sxy / sxx;
+    let r_squared = if syy == 0.0 {
+        1.0
+    } else {
+        sxy * sxy / (sxx * syy)
+    };
+    (slope, r_squared)
+}
@@ -0,0 +1,111 @@
+//! The hot-loop allocation check, with the counting allocator installed.
+
+use std::hint::black_box;
+
+use speedtest::bench::{self, parse_param};
+use speedtest::memory::CountingAlloc;
+use speedtest::{Benchmark, Expected, ParamError, RunConfig, Value};
+
+#[global_allocator]
+static ALLOC: CountingAlloc = CountingAlloc;
+
+/// A kernel over `iterations` that allocates `per_run` boxes once per run,
+/// plus one box per `chunk` iterations.
+#[derive(Clone, Copy)]
+struct Allocating {
+    per_run: u64,
+    chunk: u64,
+    iterations: u64,
+}
+
+impl Benchmark for Allocating {
+    fn name(&self) -> &'static str {
+        "allocating"
+    }
+
+    fn description(&self) -> &'static str {
+        "allocates a fixed number of boxes plus one per chunk"
+    }
+
+    fn set_param(&mut self, key: &str, value: &str) -> Result<(), ParamError> {
+        match key {
+            "iterations" => self.iterations = parse_param(key, value, 1)?,
+            _ => return Err(ParamError::Unknown(key.to_string())),
+        }
+        Ok(())
+    }
+
+    fn size_param(&self) -> &'static str {
+        "iterations"
+    }
+
+    fn clone_box(&self) -> Box<dyn Benchmark> {
+        Box::new(*self)
+    }
+
+    fn scaled(&self, factor: u64) -> Box<dyn Benchmark> {
+        Box::new(Allocating {
+            iterations: self.iterations * factor,
+            ..*self
+        })
+    }
+
+    fn work(&self) -> f64 {
+        self.iterations as f64
+    }
+
+    fn run(&self) -> Value {
+        let mut sum = 0;
+        for i in 0..self.per_run {
+            sum += *black_box(Box::new(i));
+        }
+        for i in 0..self.iterations {
+            if i % self.chunk == 0 {
+                sum += *black_box(Box::new(i));
+            }
+        }
+        Value::Int(sum as i64)
+    }
+
+    fn expected(&self) -> Expected {
+        Expected::Exact(Value::Int(0))
+    }
+}
+
+/// Whether `bench` is flagged, after checking its allocations per run.
+fn flagged(bench: &Allocating) -> bool {
+    let config = RunConfig {
+        warmup: 1,
+        runs: 3,
+        counters: false,
+        memory: true,
+    };
+    let m = bench::measure(bench, &config);
+    let heap = m.memory.and_then(|m| m.heap).expect("the allocator counts");
+    let per_run = bench.per_run + bench.iterations.div_ceil(bench.chunk);
+    assert_eq!(heap.allocations, per_run as f64);
+    heap.allocates_in_loop
+}
+
+// One test, so no other test allocates while these count.
+#[test]
+fn only_allocations_that_grow_with_the_work_are_flagged() {
+    // A fixed number per run.
+    assert!(!flagged(&Allocating {
+        per_run: 5,
+        chunk: u64::MAX,
+        iterations: 10_000,
+    }));
+    // A few per chunk, like the worker threads of `monte_carlo_pi_mt`.
+    assert!(!flagged(&Allocating {
+        per_run: 1,
+        chunk: 2_500,
+        iterations: 10_000,
+    }));
+    // One every iteration.
+    assert!(flagged(&Allocating {
+        per_run: 0,
+        chunk: 1,
+        iterations: 10_000,
+    }));
+}
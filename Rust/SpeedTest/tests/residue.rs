@@ -0,0 +1,175 @@
+//! Property tests for the residue API against the semantics of
+//! `function_call.c` and `function_call.py`.
+
+use proptest::prelude::*;
+
+use speedtest::benches::function_call::quad_res;
+use speedtest::residue::{
+    self, is_quadratic_residue, is_residue_euler, is_residue_jacobi, Modulus, ResidueError,
+};
+
+/// `quad_res` from `function_call.c`, with products in `i128` so the model
+/// is also defined where the C `long long` arithmetic would overflow.
+fn c_quad_res(n: i64, m: i64) -> i64 {
+    if m <= 0 {
+        return 0;
+    }
+    let (n, m) = (n as i128, m as i128);
+    let n_to_check = (n % m + m) % m;
+    for i in 0..m {
+        if (i * i) % m == n_to_check {
+            return 1;
+        }
+    }
+    0
+}
+
+/// `quad_res` from `function_call.py`. Python's `%` takes the sign of the
+/// divisor, which for positive `m` is Euclidean remainder.
+fn py_quad_res(n: i64, m: i64) -> i64 {
+    if m <= 0 {
+        return 0;
+    }
+    let (n, m) = (n as i128, m as i128);
+    let n_to_check = (n.rem_euclid(m) + m).rem_euclid(m);
+    (0..m).any(|i| (i * i).rem_euclid(m) == n_to_check) as i64
+}
+
+/// Primes on both sides of 2^32 and just below 2^63 and 2^64.
+const PRIMES: [u64; 5] = [
+    1_000_000_007,
+    4_294_967_291,
+    4_294_967_311,
+    9_223_372_036_854_775_783,
+    18_446_744_073_709_551_557,
+];
+
+#[test]
+fn large_primes_are_prime() {
+    for p in PRIMES {
+        assert!(residue::is_prime(p), "{}", p);
+        assert_eq!(residue::factorize(p), [(p, 1)]);
+    }
+}
+
+#[test]
+fn invalid_moduli_are_errors() {
+    assert_eq!(
+        is_quadratic_residue(4, 0),
+        Err(ResidueError::NonPositiveModulus(0))
+    );
+    assert_eq!(
+        is_quadratic_residue(4, -7),
+        Err(ResidueError::NonPositiveModulus(-7))
+    );
+    let too_large = u64::MAX as i128 + 1;
+    assert_eq!(
+        is_quadratic_residue(4, too_large),
+        Err(ResidueError::ModulusTooLarge(too_large))
+    );
+    assert_eq!(is_quadratic_residue(4, u64::MAX), Ok(true));
+}
+
+#[test]
+fn too_many_roots_are_counted_not_listed() {
+    // The roots of 0 mod 2^62 are the 2^31 multiples of 2^31.
+    let m = Modulus::new(1u64 << 62).unwrap();
+    assert_eq!(m.sqrt_count(0), 1 << 31);
+    assert_eq!(m.sqrt(0), Err(ResidueError::TooManyRoots(1 << 31)));
+    let m = Modulus::new(1u64 << 40).unwrap();
+    assert_eq!(m.sqrt(0).unwrap().len(), 1 << 20);
+    assert_eq!(m.sqrt_count(1), 4);
+}
+
+#[test]
+fn every_method_agrees_with_naive_quad_res() {
+    for m in 1..=300 {
+        residue::cross_check(m).unwrap();
+    }
+}
+
+proptest! {
+    #[test]
+    fn small_moduli_match_c_and_python(n in any::<i64>(), m in -50i64..=300) {
+        let c = c_quad_res(n, m);
+        prop_assert_eq!(py_quad_res(n, m), c);
+        prop_assert_eq!(quad_res(n, m), c);
+        match is_quadratic_residue(n, m) {
+            Ok(is_residue) => prop_assert_eq!(is_residue as i64, c),
+            Err(e) => {
+                prop_assert_eq!(e, ResidueError::NonPositiveModulus(m as i128));
+                prop_assert_eq!(c, 0);
+            }
+        }
+    }
+
+    #[test]
+    fn reduce_matches_c_normalisation(n in any::<i64>(), m in 1u64..=u64::MAX) {
+        let (wide_n, wide_m) = (n as i128, m as i128);
+        let c = (wide_n % wide_m + wide_m) % wide_m;
+        prop_assert_eq!(Modulus::new(m).unwrap().reduce(n) as i128, c);
+    }
+
+    #[test]
+    fn squares_are_residues_for_any_modulus(x in any::<u64>(), m in 1u64..=u64::MAX) {
+        let n = (x as u128 * x as u128 % m as u128) as u64;
+        prop_assert_eq!(is_quadratic_residue(n, m), Ok(true));
+        // The same class written as a negative number.
+        prop_assert_eq!(is_quadratic_residue(n as i128 - m as i128, m), Ok(true));
+    }
+
+    #[test]
+    fn large_primes_agree_with_euler_and_legendre(n in any::<u64>(), k in 0usize..PRIMES.len()) {
+        let p = PRIMES[k];
+        let expected = is_residue_euler(n % p, p);
+        prop_assert_eq!(is_residue_jacobi(n % p, p), expected);
+        prop_assert_eq!(Modulus::new(p).unwrap().is_residue(n), expected);
+    }
+
+    #[test]
+    fn composite_moduli_follow_the_crt(n in any::<u64>()) {
+        // Two primes just below 2^32 whose product still fits in a u64.
+        let (p, q) = (4_294_967_291u64, 4_294_967_279u64);
+        let m = Modulus::new(p * q).unwrap();
+        let expected = is_residue_euler(n % p, p) && is_residue_euler(n % q, q);
+        prop_assert_eq!(m.is_residue(n), expected);
+    }
+
+    #[test]
+    fn naive_kernel_widens_large_moduli(x in 0u64..20_000, m in (1u64 << 32) + 1..=i64::MAX as u64) {
+        let m = m as i64;
+        let n = (x * x) as i64;
+        prop_assert_eq!(quad_res(n, m), 1);
+        prop_assert_eq!(quad_res(n - m, m), 1);
+    }
+
+    #[test]
+    fn square_roots_contain_the_root_squared(x in any::<u64>(), m in 1u64..=u64::MAX) {
+        let n = (x as u128 * x as u128 % m as u128) as u64;
+        let m = Modulus::new(m).unwrap();
+        let count = m.sqrt_count(n);
+        let roots = match m.sqrt(n) {
+            Ok(roots) => roots,
+            Err(e) => {
+                prop_assert!(count > residue::MAX_ROOTS);
+                prop_assert_eq!(e, ResidueError::TooManyRoots(count));
+                return Ok(());
+            }
+        };
+        prop_assert_eq!(roots.len() as u64, count);
+        let m = m.get();
+        prop_assert!(roots.binary_search(&(x % m)).is_ok());
+        for &r in roots.iter().take(64) {
+            prop_assert_eq!((r as u128 * r as u128 % m as u128) as u64, n);
+        }
+    }
+
+    #[test]
+    fn tonelli_shanks_for_large_primes(n in any::<u64>(), k in 0usize..PRIMES.len()) {
+        let p = PRIMES[k];
+        match residue::sqrt_mod_prime(n, p) {
+            Some(r) => prop_assert_eq!((r as u128 * r as u128 % p as u128) as u64, n % p),
+            None => prop_assert!(!is_residue_euler(n % p, p)),
+        }
+    }
+}